@@ -1,45 +1,55 @@
-use crate::clear;
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::sitedata::{self, Status};
+use crate::submission;
+use crate::upload;
 use dialoguer::{Input, Password};
 use regex::Regex;
 use thirtyfour::error::{WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, Key, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://atcoder.jp").await?;
+const SITE: &str = "atcoder";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://atcoder.jp/login").await?;
-    if !driver
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
+    Ok(!driver
         .source()
         .await?
-        .contains("var userScreenName = \"\";")
-    {
+        .contains(sitedata::text(SITE, "logged_out")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your atcoder login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your atcoder password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("username"))
+        .find(sitedata::by(SITE, "login"))
         .await?
         .send_keys(login)
         .await?;
     driver
-        .find(By::Id("password"))
+        .find(sitedata::by(SITE, "password"))
         .await?
         .send_keys(password)
         .await?;
-    driver.find(By::Id("submit")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "login_button"))
+        .await?
+        .click()
+        .await?;
     Ok(driver.get_all_cookies().await?)
 }
 
@@ -49,22 +59,29 @@ pub async fn submit(
     language: String,
     source: String,
 ) -> WebDriverResult<()> {
-    let regex = Regex::new(r#"https://atcoder.jp/contests/(\w+)/tasks/(\w+)"#).unwrap();
+    let regex = Regex::new(&format!(
+        r"{}/contests/(\w+)/tasks/(\w+)",
+        regex::escape(&sitedata::url(SITE, "origin"))
+    ))
+    .unwrap();
     let (contest_id, task_id) = match regex.captures(&url) {
         None => {
-            println!("Invalid url");
+            submission::error("Invalid url");
             return Ok(());
         }
         Some(caps) => (caps[1].to_string(), caps[2].to_string()),
     };
-    driver
-        .goto(&format!(
-            "https://atcoder.jp/contests/{}/submit?taskScreenName={}",
-            contest_id, task_id
-        ))
-        .await?;
+    crate::goto(
+        driver,
+        &sitedata::url_with(
+            SITE,
+            "submit",
+            &[("contest", &contest_id), ("task", &task_id)],
+        ),
+    )
+    .await?;
     let (x, y) = driver
-        .find(By::Name("data.LanguageId"))
+        .find(sitedata::by(SITE, "language"))
         .await?
         .rect()
         .await?
@@ -75,44 +92,109 @@ pub async fn submit(
         .click()
         .perform()
         .await?;
-    driver.action_chain().send_keys(language).perform().await?;
+    driver
+        .action_chain()
+        .send_keys(language.clone())
+        .perform()
+        .await?;
     driver
         .action_chain()
         .send_keys(Key::Enter)
         .perform()
         .await?;
+    if upload::wanted(SITE, &source) {
+        // The page reads the chosen file into the editor.
+        let file_input = driver.find(sitedata::by(SITE, "source_file")).await?;
+        upload::attach(driver, &file_input, &source).await?;
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    } else {
+        driver
+            .execute(
+                "\
+            var editordiv = document.querySelector(arguments[1]);\
+            var editor = ace.edit(editordiv);\
+            editor.setValue(arguments[0]);\
+        ",
+                vec![
+                    serde_json::to_value(source).unwrap(),
+                    serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap(),
+                ],
+            )
+            .await?;
+    }
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let label = crate::selected_label(driver, language_field).await?;
+    let form = guard::Form {
+        language_matches: label.to_lowercase().contains(&language.to_lowercase()),
+        language: Some(label),
+        source: editor_source(driver).await?,
+    };
+    if !guard::ready(driver, form).await? {
+        return Ok(());
+    }
     driver
+        .find(sitedata::by(SITE, "submit_button"))
+        .await?
+        .click()
+        .await?;
+    follow(driver, None, true).await
+}
+
+/// Contents of the ace editor on the submit page.
+async fn editor_source(driver: &WebDriver) -> WebDriverResult<String> {
+    let value = driver
         .execute(
-            "\
-        var editordiv = document.getElementById(\"editor\");\
-        var editor = ace.edit(editordiv);\
-        editor.setValue(arguments[0]);\
-    ",
-            vec![serde_json::to_value(source).unwrap()],
+            "return ace.edit(document.querySelector(arguments[0])).getValue();",
+            vec![serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap()],
         )
         .await?;
-    driver.find(By::Id("submit")).await?.click().await?;
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    let regex = Regex::new(r"/contests/(\w+)/submissions/(\d+)").unwrap();
+    let Some(caps) = regex.captures(&url) else {
+        submission::error("Bad submission url");
+        return Ok(());
+    };
+    crate::goto(
+        driver,
+        &sitedata::url_with(SITE, "my_submissions", &[("contest", &caps[1])]),
+    )
+    .await?;
+    let row = sitedata::by_with(SITE, "submission_row", &[("id", &caps[2])]);
+    if driver.find(row.clone()).await.is_err() {
+        submission::error("Submission not found among your submissions");
+        return Ok(());
+    }
+    follow(driver, Some(row), false).await
+}
+
+/// Polls the verdict of the given row of the submissions table, or of the newest one.
+async fn follow(driver: &WebDriver, row: Option<By>, print_url: bool) -> WebDriverResult<()> {
     let mut last_verdict = "".to_string();
-    let mut printed_url = false;
+    let mut printed_url = !print_url;
     let mut times = 0;
     loop {
-        clear(last_verdict.len());
         if !printed_url {
-            if let Ok(cell) = driver.find(By::ClassName("submission-details-link")).await {
+            if let Ok(cell) = driver.find(sitedata::by(SITE, "submission_link")).await {
                 if let Some(url) = cell.attr("href").await? {
-                    println!("Submission url https://atcoder.jp{}", url);
+                    submission::submitted(
+                        SITE,
+                        &format!("{}{}", sitedata::url(SITE, "origin"), url),
+                    );
                     printed_url = true;
                 }
             }
         }
-        match iteration(driver, &mut last_verdict, &mut times).await {
+        match iteration(driver, &row, &mut last_verdict, &mut times).await {
             Ok(true) => break,
             Err(err) => match *err {
                 WebDriverErrorInner::StaleElementReference(_) => {
                     continue;
                 }
                 _ => {
-                    println!("Error while checking verdict");
+                    submission::error("Error while checking verdict");
                     break;
                 }
             },
@@ -124,23 +206,30 @@ pub async fn submit(
 
 async fn iteration(
     driver: &WebDriver,
+    row: &Option<By>,
     last_verdict: &mut String,
     times: &mut usize,
 ) -> WebDriverResult<bool> {
-    let table = driver.find(By::Tag("tbody")).await?;
-    let row = table.find(By::Tag("tr")).await?;
+    let row = match row {
+        Some(row) => driver.find(row.clone()).await?,
+        None => {
+            let table = driver.find(sitedata::by(SITE, "status_table")).await?;
+            table.find(By::Tag("tr")).await?
+        }
+    };
     let cols = row.find_all(By::Tag("td")).await?;
-    if cols.len() < 7 {
-        println!("Page format changed?");
+    let column = sitedata::index(SITE, "verdict_column");
+    if cols.len() <= column {
+        submission::error("Page format changed?");
         return Ok(true);
     }
-    let span = cols[6].find(By::Tag("span")).await?;
+    let span = cols[column].find(By::Tag("span")).await?;
     let mut verdict = span.attr("data-original-title").await?;
     if verdict.is_none() {
         verdict = span.attr("title").await?;
     }
     if verdict.is_none() {
-        println!("Page format changed?");
+        submission::error("Page format changed?");
         return Ok(true);
     }
     let mut verdict = verdict.unwrap();
@@ -151,27 +240,25 @@ async fn iteration(
     }
     let class = span.class_name().await?;
     if class.is_none() {
-        println!("Page format changed?");
+        submission::error("Page format changed?");
         return Ok(true);
     }
-    let class = class.unwrap();
-    let mut stdout = std::io::stdout();
-    let _ = execute!(
-        stdout,
-        SetForegroundColor(if class.contains("label-success") {
-            Color::Green
-        } else if class.contains("label-default") {
-            Color::Yellow
-        } else {
-            Color::Red
-        })
-    );
-    print!("{}", verdict);
-    let _ = execute!(stdout, ResetColor);
-    if !class.contains("label-default") {
-        println!();
+    let status = sitedata::verdict(SITE, &class.unwrap()).unwrap_or(Status::Rejected);
+    if status != Status::Waiting {
+        let mut details = Details::default();
+        if let Some(col) = cols.get(sitedata::index(SITE, "time_column")) {
+            details.time = Some(col.text().await?);
+        }
+        if let Some(col) = cols.get(sitedata::index(SITE, "memory_column")) {
+            details.memory = Some(col.text().await?);
+        }
+        if let Some(col) = cols.get(sitedata::index(SITE, "score_column")) {
+            details.score = Some(col.text().await?);
+        }
+        submission::finish(&verdict, status, details);
         return Ok(true);
     }
+    submission::verdict(&verdict, status);
     if *last_verdict == verdict {
         *times += 1;
         if *times > 50 {
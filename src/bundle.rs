@@ -0,0 +1,299 @@
+use crate::config;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Only C and C++ sources have their quoted includes inlined.
+const EXTENSIONS: [&str; 8] = ["c", "cc", "cpp", "cxx", "h", "hh", "hpp", "hxx"];
+
+/// Where a line of the bundled source comes from.
+pub struct Origin {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+pub struct Bundle {
+    pub source: String,
+    /// Origin of every line of `source`.
+    pub lines: Vec<Origin>,
+    /// Number of headers inlined, the file is not worth rewriting without any.
+    pub inlined: usize,
+    /// Quoted includes that were not found and are left in place.
+    pub warnings: Vec<String>,
+}
+
+struct Bundler {
+    include: Regex,
+    pragma_once: Regex,
+    include_paths: Vec<PathBuf>,
+    strip_comments: bool,
+    /// Headers with `#pragma once` that are already inlined.
+    once: HashSet<PathBuf>,
+    /// Files being expanded, to stop include cycles.
+    stack: Vec<PathBuf>,
+    bundle: Bundle,
+}
+
+pub fn applies(file: &Path) -> bool {
+    file.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Inlines the quoted includes of `source`, read from `file`, that are found next to the
+/// including file or in the configured include paths. Other includes are left for the judge.
+pub fn bundle(file: &Path, source: &str) -> Result<Bundle, String> {
+    let settings = &config::get().bundle;
+    let mut bundler = Bundler {
+        include: Regex::new(r#"^\s*#\s*include\s*"([^"]+)""#).unwrap(),
+        pragma_once: Regex::new(r"^\s*#\s*pragma\s+once\b").unwrap(),
+        include_paths: settings.include_paths.clone(),
+        strip_comments: settings.strip_comments,
+        once: HashSet::new(),
+        stack: Vec::new(),
+        bundle: Bundle {
+            source: String::new(),
+            lines: Vec::new(),
+            inlined: 0,
+            warnings: Vec::new(),
+        },
+    };
+    bundler.add(file, Some(source))?;
+    Ok(bundler.bundle)
+}
+
+impl Bundler {
+    fn resolve(&self, from: &Path, name: &str) -> Option<PathBuf> {
+        let dir = from.parent().unwrap_or(Path::new(""));
+        std::iter::once(dir)
+            .chain(self.include_paths.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Adds `file`, with `source` as its contents for the file being submitted itself.
+    fn add(&mut self, file: &Path, source: Option<&str>) -> Result<(), String> {
+        let inlined = source.is_none();
+        let canonical = std::fs::canonicalize(file)
+            .map_err(|err| format!("Failed to read {}: {}", file.display(), err))?;
+        // An include guard would make the compiler skip these as well.
+        if self.once.contains(&canonical) || self.stack.contains(&canonical) {
+            return Ok(());
+        }
+        let mut text = match source {
+            Some(source) => source.to_string(),
+            None => std::fs::read_to_string(file)
+                .map_err(|err| format!("Failed to read {}: {}", file.display(), err))?,
+        };
+        let strip = inlined && self.strip_comments;
+        if strip {
+            text = strip_comments(&text);
+        }
+        if inlined {
+            self.bundle.inlined += 1;
+        }
+        self.stack.push(canonical.clone());
+        for (index, line) in text.lines().enumerate() {
+            if self.pragma_once.is_match(line) {
+                self.once.insert(canonical.clone());
+                continue;
+            }
+            if let Some(caps) = self.include.captures(line) {
+                match self.resolve(file, &caps[1]) {
+                    Some(path) => {
+                        self.add(&path, None)?;
+                        continue;
+                    }
+                    None => self.bundle.warnings.push(format!(
+                        "{}:{}: {} not found, leaving the include as is",
+                        file.display(),
+                        index + 1,
+                        &caps[1]
+                    )),
+                }
+            }
+            if strip && line.trim().is_empty() {
+                continue;
+            }
+            self.bundle.source.push_str(line);
+            self.bundle.source.push('\n');
+            self.bundle.lines.push(Origin {
+                file: file.to_path_buf(),
+                line: index + 1,
+            });
+        }
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// Removes comments outside string and character literals. Newlines inside block comments stay,
+/// so line numbers keep pointing at the original file.
+fn strip_comments(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                // A backslash at the end of the line continues the comment.
+                if chars[i] == '\\' && chars.get(i + 1) == Some(&'\n') {
+                    result.push('\n');
+                    i += 1;
+                }
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    result.push('\n');
+                }
+                i += 1;
+            }
+            i += 2;
+            result.push(' ');
+        } else if c == 'R'
+            && next == Some('"')
+            && !(i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_'))
+        {
+            // Raw string literal, R"delimiter( ... )delimiter".
+            let open = chars[i + 2..].iter().position(|&c| c == '(');
+            let delimiter: String = open
+                .map(|open| chars[i + 2..i + 2 + open].iter().collect())
+                .unwrap_or_default();
+            let close: Vec<char> = format!("){}\"", delimiter).chars().collect();
+            let start = i;
+            i += 2;
+            while i < chars.len() && !chars[i..].starts_with(&close) {
+                i += 1;
+            }
+            i = (i + close.len()).min(chars.len());
+            result.extend(&chars[start..i]);
+        } else if c == '"' || c == '\'' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            result.extend(&chars[start..i]);
+        } else {
+            result.push(c);
+            i += 1;
+        }
+    }
+    result
+}
+
+impl Bundle {
+    pub fn origin(&self, line: usize) -> Option<&Origin> {
+        self.lines.get(line.checked_sub(1)?)
+    }
+
+    /// Rewrites `name:line:` positions in compiler output to the files the lines come from.
+    pub fn translate(&self, name: &str, text: &str) -> String {
+        let regex = Regex::new(&format!(r"{}:(\d+):", regex::escape(name))).unwrap();
+        regex
+            .replace_all(text, |caps: &regex::Captures| {
+                let line = caps[1].parse().unwrap_or(0);
+                match self.origin(line) {
+                    Some(origin) => format!("{}:{}:", origin.file.display(), origin.line),
+                    None => caps[0].to_string(),
+                }
+            })
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for every test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("submitter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn strip_comments_keeps_literals_and_lines() {
+        assert_eq!(strip_comments("int a; // b\nint c;\n"), "int a; \nint c;\n");
+        assert_eq!(strip_comments("a /* b\nc */ d\n"), "a \n  d\n");
+        assert_eq!(
+            strip_comments("s = \"// no\"; c = '/'; // yes\n"),
+            "s = \"// no\"; c = '/'; \n"
+        );
+        assert_eq!(
+            strip_comments("r = R\"x(/* )\" */)x\";\n"),
+            "r = R\"x(/* )\" */)x\";\n"
+        );
+        assert_eq!(strip_comments("// a \\\nb\nc\n"), "\n\nc\n");
+    }
+
+    #[test]
+    fn bundle_inlines_quoted_includes_once() {
+        let dir = scratch("bundle");
+        std::fs::write(dir.join("a.h"), "#pragma once\nint a;\n").unwrap();
+        std::fs::write(dir.join("b.h"), "#include \"a.h\"\nint b;\n").unwrap();
+        let file = dir.join("main.cpp");
+        let source = "#include <vector>\n#include \"a.h\"\n#include \"b.h\"\n#include \"c.h\"\nint main() {}\n";
+        std::fs::write(&file, source).unwrap();
+        let bundle = bundle(&file, source).unwrap();
+        assert_eq!(
+            bundle.source,
+            "#include <vector>\nint a;\nint b;\n#include \"c.h\"\nint main() {}\n"
+        );
+        assert_eq!(bundle.inlined, 2);
+        assert_eq!(bundle.warnings.len(), 1);
+        let origin = bundle.origin(3).unwrap();
+        assert_eq!(
+            (origin.file.as_path(), origin.line),
+            (dir.join("b.h").as_path(), 2)
+        );
+        assert!(bundle.origin(6).is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bundle_stops_include_cycles() {
+        let dir = scratch("cycle");
+        std::fs::write(dir.join("a.h"), "#include \"b.h\"\nint a;\n").unwrap();
+        std::fs::write(dir.join("b.h"), "#include \"a.h\"\nint b;\n").unwrap();
+        let file = dir.join("main.cpp");
+        let source = "#include \"a.h\"\n";
+        std::fs::write(&file, source).unwrap();
+        assert_eq!(bundle(&file, source).unwrap().source, "int b;\nint a;\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn translate_points_at_the_original_files() {
+        let bundle = Bundle {
+            source: String::new(),
+            lines: vec![
+                Origin {
+                    file: PathBuf::from("main.cpp"),
+                    line: 1,
+                },
+                Origin {
+                    file: PathBuf::from("lib.h"),
+                    line: 7,
+                },
+            ],
+            inlined: 1,
+            warnings: Vec::new(),
+        };
+        assert_eq!(
+            bundle.translate("x.cpp", "x.cpp:2:5: error\nx.cpp:9: note\ny.cpp:1:"),
+            "lib.h:7:5: error\nx.cpp:9: note\ny.cpp:1:"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::tester::{self, Build};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Output of the solution handed to a testlib checker.
+const OUTPUT: &str = ".submitter/output.txt";
+const CHECKER_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// How the output of a sample is compared with the expected one, kept in `problem.json`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Checker {
+    /// Lines are equal apart from trailing whitespace and trailing empty lines.
+    #[default]
+    Lines,
+    /// Whitespace-separated tokens are equal.
+    Tokens,
+    /// Tokens are equal ignoring case, for YES/NO answers.
+    IgnoreCase,
+    /// Numbers differ by at most `epsilon`, absolutely or relatively, other tokens are equal.
+    Float {
+        #[serde(default = "default_epsilon")]
+        epsilon: f64,
+    },
+    /// A testlib checker started as `checker input output answer`, an executable or a source to
+    /// compile.
+    Testlib { path: PathBuf },
+}
+
+pub enum Verdict {
+    Accepted,
+    /// With the reason when there is more to say than the diff.
+    WrongAnswer(Option<String>),
+    /// The checker itself crashed or reported a failure.
+    Failed(String),
+}
+
+/// A checker ready to judge outputs, compiled if it is a testlib one.
+pub struct Judge {
+    checker: Checker,
+    program: Option<Build>,
+}
+
+fn default_epsilon() -> f64 {
+    1e-6
+}
+
+/// Parses `--checker`: `lines`, `tokens`, `ignore-case`, `float[:epsilon]` or a testlib checker.
+pub fn parse(value: &str) -> Option<Checker> {
+    match value {
+        "lines" => return Some(Checker::Lines),
+        "tokens" => return Some(Checker::Tokens),
+        "ignore-case" => return Some(Checker::IgnoreCase),
+        "float" => {
+            return Some(Checker::Float {
+                epsilon: default_epsilon(),
+            })
+        }
+        _ => {}
+    }
+    if let Some(epsilon) = value.strip_prefix("float:") {
+        return Some(Checker::Float {
+            epsilon: epsilon.parse().ok()?,
+        });
+    }
+    let path = PathBuf::from(value);
+    path.is_file().then_some(Checker::Testlib { path })
+}
+
+impl Checker {
+    pub fn describe(&self) -> String {
+        match self {
+            Checker::Lines => "lines".to_string(),
+            Checker::Tokens => "tokens".to_string(),
+            Checker::IgnoreCase => "tokens ignoring case".to_string(),
+            Checker::Float { epsilon } => format!("numbers with error {}", epsilon),
+            Checker::Testlib { path } => format!("testlib {}", path.display()),
+        }
+    }
+}
+
+/// Compiles a testlib checker given as source.
+pub async fn prepare(checker: Checker) -> Option<Judge> {
+    let Checker::Testlib { path } = &checker else {
+        return Some(Judge {
+            checker,
+            program: None,
+        });
+    };
+    let program = tester::helper(path).await?;
+    Some(Judge {
+        checker,
+        program: Some(program),
+    })
+}
+
+impl Judge {
+    pub async fn check(&self, input: &Path, output: &str, answer: &Path) -> Verdict {
+        let expected = std::fs::read_to_string(answer).unwrap_or_default();
+        match &self.checker {
+            Checker::Lines => verdict(tester::same_output(&expected, output), None),
+            Checker::Tokens => {
+                compare_tokens(&expected, output, |expected, actual| expected == actual)
+            }
+            Checker::IgnoreCase => compare_tokens(&expected, output, same_ignoring_case),
+            Checker::Float { epsilon } => compare_tokens(&expected, output, |expected, actual| {
+                same_number(expected, actual, *epsilon)
+            }),
+            Checker::Testlib { .. } => self.run_testlib(input, output, answer).await,
+        }
+    }
+
+    async fn run_testlib(&self, input: &Path, output: &str, answer: &Path) -> Verdict {
+        let Some(program) = &self.program else {
+            return Verdict::Failed("Checker is not compiled".to_string());
+        };
+        let written =
+            tester::create_parent(Path::new(OUTPUT)).and_then(|_| std::fs::write(OUTPUT, output));
+        if let Err(err) = written {
+            return Verdict::Failed(format!("Failed to write {}: {}", OUTPUT, err));
+        }
+        // Paths go as positional parameters so they need no quoting.
+        let result = Command::new("sh")
+            .arg("-c")
+            .arg(format!("exec {} \"$@\"", program.run))
+            .arg("checker")
+            .arg(input)
+            .arg(OUTPUT)
+            .arg(answer)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+        let result = match tokio::time::timeout(CHECKER_TIME_LIMIT, result).await {
+            Err(_) => return Verdict::Failed("Checker timed out".to_string()),
+            Ok(Err(err)) => return Verdict::Failed(format!("Failed to run the checker: {}", err)),
+            Ok(Ok(result)) => result,
+        };
+        let mut message = String::from_utf8_lossy(&result.stderr).trim().to_string();
+        if message.is_empty() {
+            message = String::from_utf8_lossy(&result.stdout).trim().to_string();
+        }
+        testlib_verdict("Checker", result.status, message)
+    }
+}
+
+/// Verdict from the exit code of a testlib checker or interactor: 0 ok, 1 wrong answer,
+/// 2 presentation error, anything else a failure of the `program` itself.
+pub fn testlib_verdict(program: &str, status: ExitStatus, message: String) -> Verdict {
+    match status.code() {
+        Some(0) => Verdict::Accepted,
+        Some(1 | 2) => Verdict::WrongAnswer((!message.is_empty()).then_some(message)),
+        Some(code) => Verdict::Failed(format!("{} exited with {}: {}", program, code, message)),
+        None => Verdict::Failed(format!("{} {}", program, status)),
+    }
+}
+
+fn verdict(accepted: bool, reason: Option<String>) -> Verdict {
+    if accepted {
+        Verdict::Accepted
+    } else {
+        Verdict::WrongAnswer(reason)
+    }
+}
+
+fn same_ignoring_case(expected: &str, actual: &str) -> bool {
+    expected.to_lowercase() == actual.to_lowercase()
+}
+
+fn same_number(expected: &str, actual: &str, epsilon: f64) -> bool {
+    match (expected.parse::<f64>(), actual.parse::<f64>()) {
+        (Ok(expected), Ok(actual)) if expected.is_finite() && actual.is_finite() => {
+            let error = (expected - actual).abs();
+            error <= epsilon || error <= epsilon * expected.abs()
+        }
+        _ => expected == actual,
+    }
+}
+
+fn compare_tokens(expected: &str, actual: &str, same: impl Fn(&str, &str) -> bool) -> Verdict {
+    let expected: Vec<_> = expected.split_whitespace().collect();
+    let actual: Vec<_> = actual.split_whitespace().collect();
+    if let Some(index) =
+        (0..expected.len().min(actual.len())).find(|&index| !same(expected[index], actual[index]))
+    {
+        return verdict(
+            false,
+            Some(format!(
+                "Token {} differs: expected {}, found {}",
+                index + 1,
+                expected[index],
+                actual[index]
+            )),
+        );
+    }
+    let reason = format!("Expected {} tokens, found {}", expected.len(), actual.len());
+    verdict(expected.len() == actual.len(), Some(reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(verdict: Verdict) -> Option<String> {
+        match verdict {
+            Verdict::Accepted => None,
+            Verdict::WrongAnswer(reason) => reason,
+            Verdict::Failed(message) => panic!("failed: {}", message),
+        }
+    }
+
+    #[test]
+    fn tokens_ignore_whitespace() {
+        let exact = |expected: &str, actual: &str| expected == actual;
+        assert!(matches!(
+            compare_tokens("1 2\n3\n", " 1\t2 3", exact),
+            Verdict::Accepted
+        ));
+        assert_eq!(
+            reason(compare_tokens("1 2 3", "1 5 3", exact)).unwrap(),
+            "Token 2 differs: expected 2, found 5"
+        );
+        assert_eq!(
+            reason(compare_tokens("1 2", "1 2 3", exact)).unwrap(),
+            "Expected 2 tokens, found 3"
+        );
+    }
+
+    #[test]
+    fn ignore_case_compares_tokens_in_any_case() {
+        assert!(matches!(
+            compare_tokens("YES\nNo\n", "yes NO", same_ignoring_case),
+            Verdict::Accepted
+        ));
+        assert!(reason(compare_tokens("YES", "NO", same_ignoring_case)).is_some());
+    }
+
+    #[test]
+    fn float_allows_absolute_or_relative_error() {
+        let same = |expected: &str, actual: &str| same_number(expected, actual, 1e-6);
+        assert!(matches!(
+            compare_tokens("0.5 1000000", "0.5000009 1000000.9", same),
+            Verdict::Accepted
+        ));
+        assert!(reason(compare_tokens("0.5", "0.500002", same)).is_some());
+        assert!(matches!(
+            compare_tokens("answer 1.0", "answer 1", same),
+            Verdict::Accepted
+        ));
+        assert!(reason(compare_tokens("answer", "Answer", same)).is_some());
+        assert!(reason(compare_tokens("1", "nan", same)).is_some());
+    }
+}
@@ -1,31 +1,38 @@
-use crate::clear;
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::sitedata::{self, Status};
+use crate::submission;
 use dialoguer::{Input, Password};
+use regex::Regex;
 use thirtyfour::error::WebDriverResult;
 use thirtyfour::{By, Cookie, Key, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://codechef.com/").await?;
+const SITE: &str = "codechef";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://codechef.com/").await?;
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     tokio::time::sleep(std::time::Duration::from_secs(4)).await;
     let source = driver.source().await?;
-    if !source.contains("Sign Up") {
+    Ok(!source.contains(sitedata::text(SITE, "logged_out")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
-    driver.goto("https://www.codechef.com/login").await?;
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your codechef login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your codechef password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
         .action_chain()
@@ -43,16 +50,16 @@ pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<
     for _ in 0..10 {
         tokio::time::sleep(std::time::Duration::from_secs(10)).await;
         if !driver
-            .find(By::Tag("title"))
+            .find(sitedata::by(SITE, "title"))
             .await?
             .text()
             .await?
-            .starts_with("CodeChef Login")
+            .starts_with(sitedata::text(SITE, "login_title"))
         {
             return Ok(driver.get_all_cookies().await?);
         }
     }
-    eprintln!("Failed to login");
+    submission::error("Failed to login");
     Err(thirtyfour::error::WebDriverError::ParseError(
         "Failed to login".to_string(),
     ))
@@ -65,11 +72,15 @@ pub async fn submit(
     source: String,
 ) -> WebDriverResult<()> {
     driver.maximize_window().await?;
-    driver.goto(&url).await?;
+    crate::goto(driver, &url).await?;
     tokio::time::sleep(std::time::Duration::from_secs(4)).await;
-    let language_select = driver.find(By::Id("language-select")).await?;
+    let language_select = driver.find(sitedata::by(SITE, "language")).await?;
     language_select.click().await?;
-    driver.action_chain().send_keys(language).perform().await?;
+    driver
+        .action_chain()
+        .send_keys(language.clone())
+        .perform()
+        .await?;
     let center = language_select.rect().await?.icenter();
     driver
         .action_chain()
@@ -80,25 +91,41 @@ pub async fn submit(
     driver
         .execute(
             "\
-        var editordiv = document.getElementById(\"submit-ide-v2\");\
+        var editordiv = document.querySelector(arguments[1]);\
         var editor = ace.edit(editordiv);\
         editor.setValue(arguments[0]);\
     ",
-            vec![serde_json::to_value(source).unwrap()],
+            vec![
+                serde_json::to_value(source).unwrap(),
+                serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap(),
+            ],
         )
         .await?;
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    driver.find(By::Id("submit_btn")).await?.click().await?;
-    let mut stdout = std::io::stdout();
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let label = crate::selected_label(driver, language_field).await?;
+    let form = guard::Form {
+        language_matches: label.to_lowercase().contains(&language.to_lowercase()),
+        language: Some(label),
+        source: editor_source(driver).await?,
+    };
+    if !guard::ready(driver, form).await? {
+        return Ok(());
+    }
+    driver
+        .find(sitedata::by(SITE, "submit_button"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
     driver
-        .find(By::Id("vertical-tab-panel-1"))
+        .find(sitedata::by(SITE, "submissions_tab"))
         .await?
         .click()
         .await?;
     let id = loop {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        let tbody = driver.find_all(By::Tag("tbody")).await?;
+        let tbody = driver.find_all(sitedata::by(SITE, "submissions")).await?;
         if tbody.is_empty() {
             continue;
         }
@@ -107,49 +134,68 @@ pub async fn submit(
             break divs[1].text().await?;
         }
     };
-    driver
-        .goto(&format!("https://www.codechef.com/viewsolution/{}", id))
+    let solution_url = sitedata::url_with(SITE, "solution", &[("id", &id)]);
+    crate::goto(driver, &solution_url).await?;
+    submission::submitted(SITE, &solution_url);
+    follow(driver).await
+}
+
+/// Contents of the ace editor on the submit page.
+async fn editor_source(driver: &WebDriver) -> WebDriverResult<String> {
+    let value = driver
+        .execute(
+            "return ace.edit(document.querySelector(arguments[0])).getValue();",
+            vec![serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap()],
+        )
         .await?;
-    println!(
-        "Submission url https://www.codechef.com/viewsolution/{}",
-        id
-    );
-    let _ = execute!(stdout, SetForegroundColor(Color::Yellow));
-    print!("Judging");
-    let _ = execute!(stdout, ResetColor);
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    let regex = Regex::new(r"/viewsolution/(\w+)").unwrap();
+    let Some(caps) = regex.captures(&url) else {
+        submission::error("Bad submission url");
+        return Ok(());
+    };
+    crate::goto(
+        driver,
+        &sitedata::url_with(SITE, "solution", &[("id", &caps[1])]),
+    )
+    .await?;
+    follow(driver).await
+}
+
+/// Polls the solution page that is open until the verdict and test results are shown.
+async fn follow(driver: &WebDriver) -> WebDriverResult<()> {
+    submission::verdict("Judging", Status::Waiting);
     tokio::time::sleep(std::time::Duration::from_secs(4)).await;
     loop {
-        let Ok(verdict) = driver
-            .find(By::ClassName("_status__container_1xnpw_48"))
-            .await
-        else {
+        let Ok(verdict) = driver.find(sitedata::by(SITE, "verdict")).await else {
             driver.refresh().await?;
             tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
             continue;
         };
-        if verdict.text().await?.starts_with("Submission Queued") {
+        if verdict
+            .text()
+            .await?
+            .starts_with(sitedata::text(SITE, "queued"))
+        {
             driver.refresh().await?;
             tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
             continue;
         }
-        clear(7);
         let full_verdict = verdict.find(By::Tag("span")).await?.text().await?;
-        let accepted = full_verdict.contains("Correct Answer")
-            || full_verdict.contains("You got it right!")
-            || full_verdict.contains("Excellent work!")
-            || full_verdict.contains("Awesome, you nailed it!");
-        let _ = execute!(
-            stdout,
-            SetForegroundColor(if accepted { Color::Green } else { Color::Red })
-        );
-        println!("{}", full_verdict);
-        let _ = execute!(stdout, ResetColor);
-        if full_verdict == "Compilation Error".to_string() {
+        let status = match sitedata::verdict(SITE, &full_verdict) {
+            Some(Status::Accepted) => Status::Accepted,
+            _ => Status::Rejected,
+        };
+        submission::finish(&full_verdict, status, Details::default());
+        if full_verdict == sitedata::text(SITE, "compilation_error") {
             return Ok(());
         }
         let mut tries = 0;
         let table = loop {
-            match driver.find(By::ClassName("status-table")).await {
+            match driver.find(sitedata::by(SITE, "status_table")).await {
                 Ok(table) => break table,
                 Err(_) => {
                     tries += 1;
@@ -161,14 +207,15 @@ pub async fn submit(
             }
         };
         let rows = table.find_all(By::Tag("tr")).await?;
-        println!("Subtask Task Result");
+        output::text("Subtask Task Result");
         for row in rows.into_iter().skip(1) {
-            if row.class_name().await? == Some("skip".to_string())
-                || row.class_name().await? == Some("subtask-result".to_string())
+            let class = row.class_name().await?.unwrap_or_default();
+            if class == sitedata::text(SITE, "row_skip")
+                || class == sitedata::text(SITE, "row_subtask")
             {
                 continue;
             }
-            let is_accepted = row.class_name().await? == Some("correct".to_string());
+            let is_accepted = class == sitedata::text(SITE, "row_correct");
             let cells = row.find_all(By::Tag("td")).await?;
             if cells.len() < 3 {
                 continue;
@@ -181,16 +228,16 @@ pub async fn submit(
                 .replace("\n", "")
                 .replace("\"", "")
                 .replace("<br>", " ");
-            let _ = execute!(
-                stdout,
-                SetForegroundColor(if is_accepted {
-                    Color::Green
+            output::test(
+                &subtask,
+                &task,
+                &result,
+                if is_accepted {
+                    Status::Accepted
                 } else {
-                    Color::Red
-                })
+                    Status::Rejected
+                },
             );
-            println!("{:7} {:4} {}", subtask, task, result);
-            let _ = execute!(stdout, ResetColor);
         }
         break;
     }
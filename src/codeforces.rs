@@ -1,15 +1,20 @@
-use crate::clear;
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::sitedata::{self, Status};
+use crate::submission;
+use crate::upload;
 use dialoguer::{Input, Password};
+use regex::Regex;
 use thirtyfour::error::{WebDriverError, WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, WebDriver};
 
+const SITE: &str = "codeforces";
+
 async fn is_cloudflare(driver: &WebDriver) -> WebDriverResult<bool> {
-    Ok(driver.source().await?.contains(
-        "<body><p>Please wait. Your browser is being checked. It may take a few seconds...</p>",
-    ))
+    Ok(driver
+        .source()
+        .await?
+        .contains(sitedata::text(SITE, "cloudflare")))
 }
 
 async fn skip_cloudflare(driver: &WebDriver) -> WebDriverResult<()> {
@@ -17,8 +22,8 @@ async fn skip_cloudflare(driver: &WebDriver) -> WebDriverResult<()> {
     while is_cloudflare(driver).await? {
         times += 1;
         if times == 10 {
-            eprintln!("Cannot bypass cloudflare captcha, please submit manually");
-            eprintln!("Will clear cookies, may help");
+            submission::error("Cannot bypass cloudflare captcha, please submit manually");
+            submission::error("Will clear cookies, may help");
             return Err(WebDriverError::ParseError(
                 "Cannot bypass cloudflare captcha".to_string(),
             ));
@@ -28,37 +33,49 @@ async fn skip_cloudflare(driver: &WebDriver) -> WebDriverResult<()> {
     Ok(())
 }
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://mirror.codeforces.com/").await?;
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://mirror.codeforces.com/enter").await?;
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
     skip_cloudflare(driver).await?;
-    if driver.current_url().await?.as_str() != "https://mirror.codeforces.com/enter" {
+    Ok(driver.current_url().await?.as_str() != crate::site_url(&sitedata::url(SITE, "login")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your codeforces login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your codeforces password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("handleOrEmail"))
+        .find(sitedata::by(SITE, "login"))
         .await?
         .send_keys(login)
         .await?;
     driver
-        .find(By::Id("password"))
+        .find(sitedata::by(SITE, "password"))
         .await?
         .send_keys(password)
         .await?;
-    driver.find(By::Id("remember")).await?.click().await?;
-    driver.find(By::ClassName("submit")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "remember"))
+        .await?
+        .click()
+        .await?;
+    driver
+        .find(sitedata::by(SITE, "login_button"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
     skip_cloudflare(driver).await?;
     Ok(driver.get_all_cookies().await?)
@@ -72,7 +89,7 @@ pub async fn submit(
 ) -> WebDriverResult<()> {
     let pos = match url.rfind("/problem/") {
         None => {
-            eprintln!("Bad url");
+            submission::error("Bad url");
             return Ok(());
         }
         Some(pos) => pos,
@@ -81,67 +98,121 @@ pub async fn submit(
     let (submit_url, status_url) = if url.contains("problemset") {
         let slash = url[pos + 9..].find('/').unwrap();
         (
-            "https://mirror.codeforces.com/problemset/submit".to_string(),
-            format!(
-                "https://codeforces.com/problemset/submission/{}/",
-                &url[pos + 9..pos + 9 + slash]
+            sitedata::url(SITE, "problemset_submit"),
+            sitedata::url_with(
+                SITE,
+                "problemset_submission",
+                &[("contest", &url[pos + 9..pos + 9 + slash])],
             ),
         )
     } else {
         (
-            url[..pos].replace("https://codeforces.com", "https://mirror.codeforces.com")
-                + "/submit",
+            url[..pos].replace(
+                &sitedata::url(SITE, "origin"),
+                &sitedata::url(SITE, "mirror"),
+            ) + "/submit",
             format!("{}/submission/", &url[..pos]),
         )
     };
-    driver.goto(&submit_url).await?;
+    crate::goto(driver, &submit_url).await?;
     skip_cloudflare(driver).await?;
-    match driver.find(By::Name("submittedProblemCode")).await {
+    match driver.find(sitedata::by(SITE, "problem_code")).await {
         Ok(element) => {
             element.send_keys(id).await?;
         }
         Err(_) => {
-            let selector = driver.find(By::Name("submittedProblemIndex")).await?;
+            let selector = driver.find(sitedata::by(SITE, "problem_index")).await?;
             if !crate::select_value(selector, id.as_str()).await? {
-                eprintln!("Bad id");
+                submission::error("Bad id");
                 return Ok(());
             }
         }
     }
-    let element = driver.find(By::Name("programTypeId")).await?;
-    if !crate::select_value(element, get_language(language).as_str()).await? {
-        eprintln!("Bad language");
+    let language_id = get_language(language);
+    let element = driver.find(sitedata::by(SITE, "language")).await?;
+    if !crate::select_value(element, language_id.as_str()).await? {
+        submission::error("Bad language");
+        return Ok(());
+    }
+    let filled = if upload::wanted(SITE, &source) {
+        let file_input = driver.find(sitedata::by(SITE, "source_file")).await?;
+        upload::attach(driver, &file_input, &source).await?;
+        upload::read_back(driver, &file_input).await?
+    } else {
+        driver
+            .find(sitedata::by(SITE, "toggle_editor"))
+            .await?
+            .click()
+            .await?;
+        let input_field = driver.find(sitedata::by(SITE, "source")).await?;
+        crate::set_value(driver, input_field.clone(), source).await?;
+        input_field.value().await?.unwrap_or_default()
+    };
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let form = guard::Form {
+        language: Some(crate::selected_label(driver, language_field.clone()).await?),
+        language_matches: language_field.value().await? == Some(language_id),
+        source: filled,
+    };
+    if !guard::ready(driver, form).await? {
         return Ok(());
     }
     driver
-        .find(By::Id("toggleEditorCheckbox"))
+        .find(sitedata::by(SITE, "submit_button"))
         .await?
         .click()
         .await?;
-    let input_field = driver.find(By::Id("sourceCodeTextarea")).await?;
-    crate::set_value(driver, input_field, source).await?;
-    driver.find(By::ClassName("submit")).await?.click().await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
     skip_cloudflare(driver).await?;
     if driver
         .current_url()
         .await?
         .as_str()
-        .starts_with(&submit_url)
+        .starts_with(&crate::site_url(&submit_url))
     {
-        let error = driver.find_all(By::ClassName("error")).await?;
-        eprintln!("Error submitting: ");
-        for element in error {
-            eprint!("{}", element.text().await?);
+        let mut message = "Error submitting: ".to_string();
+        for element in driver.find_all(sitedata::by(SITE, "error")).await? {
+            message += &element.text().await?;
         }
+        submission::error(&message);
         return Ok(());
     }
+    follow(driver, None, Some(&status_url)).await
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    let regex =
+        Regex::new(r"/(contest|gym|problemset/submission)/(\d+)/(?:submission/)?(\d+)").unwrap();
+    let Some(caps) = regex.captures(&url) else {
+        submission::error("Bad submission url");
+        return Ok(());
+    };
+    let kind = if &caps[1] == "gym" { "gym" } else { "contest" };
+    crate::goto(
+        driver,
+        &sitedata::url_with(SITE, "my", &[("kind", kind), ("contest", &caps[2])]),
+    )
+    .await?;
+    skip_cloudflare(driver).await?;
+    let row = sitedata::by_with(SITE, "submission_row", &[("id", &caps[3])]);
+    if driver.find(row.clone()).await.is_err() {
+        submission::error("Submission not found among your submissions");
+        return Ok(());
+    }
+    follow(driver, Some(row), None).await
+}
+
+/// Polls the verdict of the given status row, or of the newest one, until it is final.
+async fn follow(
+    driver: &WebDriver,
+    row: Option<By>,
+    status_url: Option<&str>,
+) -> WebDriverResult<()> {
     let mut last_verdict = "".to_string();
     let mut printed_url = false;
     loop {
-        clear(last_verdict.len());
-        if !printed_url {
-            if let Ok(id_cell) = driver.find(By::ClassName("id-cell")).await {
+        if let Some(status_url) = status_url.filter(|_| !printed_url) {
+            if let Ok(id_cell) = driver.find(sitedata::by(SITE, "id_cell")).await {
                 if let Some(id) = id_cell
                     .find(By::Tag("a"))
                     .await?
@@ -149,11 +220,11 @@ pub async fn submit(
                     .await?
                 {
                     printed_url = true;
-                    println!("Submission url {}{}", status_url, id);
+                    submission::submitted(SITE, &format!("{}{}", status_url, id));
                 }
             }
         }
-        match iteration(driver, &mut last_verdict).await {
+        match iteration(driver, &row, &mut last_verdict).await {
             Ok(res) => {
                 if res {
                     break;
@@ -171,50 +242,58 @@ pub async fn submit(
     Ok(())
 }
 
-async fn iteration(driver: &WebDriver, last_verdict: &mut String) -> WebDriverResult<bool> {
-    let mut stdout = std::io::stdout();
-    let cell = driver.find(By::ClassName("status-cell")).await?;
+async fn iteration(
+    driver: &WebDriver,
+    row: &Option<By>,
+    last_verdict: &mut String,
+) -> WebDriverResult<bool> {
+    let cell = match row {
+        Some(row) => {
+            driver
+                .find(row.clone())
+                .await?
+                .find(sitedata::by(SITE, "status_cell"))
+                .await?
+        }
+        None => driver.find(sitedata::by(SITE, "status_cell")).await?,
+    };
     let verdict = cell.text().await?;
-    let (is_waiting, is_accepted) = match cell.find(By::Tag("span")).await {
+    let status = match cell.find(By::Tag("span")).await {
         Ok(mut verdict) => {
-            if verdict.class_name().await? == Some("submissionVerdictWrapper".to_string()) {
-                verdict = verdict.find(By::Tag("span")).await?;
+            if let Ok(wrapper) = cell.find(sitedata::by(SITE, "verdict_wrapper")).await {
+                verdict = wrapper.find(By::Tag("span")).await?;
             }
-            (
-                verdict.class_name().await? == Some("verdict-waiting".to_string()),
-                verdict.class_name().await? == Some("verdict-accepted".to_string()),
-            )
+            let class = verdict.class_name().await?.unwrap_or_default();
+            sitedata::verdict(SITE, &class).unwrap_or(Status::Rejected)
         }
         Err(_) => {
-            if verdict.trim() == "Compilation error" {
-                (false, false)
+            if verdict.trim() == sitedata::text(SITE, "compilation_error") {
+                Status::Rejected
             } else {
-                (true, false)
+                Status::Waiting
             }
         }
     };
-    let _ = execute!(
-        stdout,
-        SetForegroundColor(if is_waiting {
-            Color::Yellow
-        } else if is_accepted {
-            Color::Green
-        } else {
-            Color::Red
-        })
-    );
-    print!("{}", verdict);
-    let _ = execute!(stdout, ResetColor);
+    let is_waiting = status == Status::Waiting;
+    if !is_waiting {
+        let row = cell.find(By::XPath("./ancestor::tr")).await?;
+        let mut details = Details::default();
+        if let Ok(cell) = row.find(sitedata::by(SITE, "time_cell")).await {
+            details.time = Some(cell.text().await?.trim().to_string());
+        }
+        if let Ok(cell) = row.find(sitedata::by(SITE, "memory_cell")).await {
+            details.memory = Some(cell.text().await?.trim().to_string());
+        }
+        submission::finish(&verdict, status, details);
+        return Ok(true);
+    }
+    submission::verdict(&verdict, status);
     if verdict == *last_verdict && is_waiting {
         tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         driver.refresh().await?;
         skip_cloudflare(driver).await?;
         return Ok(false);
     }
-    if !is_waiting {
-        println!();
-        return Ok(true);
-    }
     *last_verdict = verdict;
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     Ok(false)
@@ -0,0 +1,102 @@
+use crate::lint::Level;
+use crate::output;
+use crate::tester::Language;
+use crate::transform::Transform;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Looked up in the working directory first, then as `~/.submitter.json`.
+const FILE: &str = "submitter.json";
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub bundle: BundleConfig,
+    /// Applied in order before submitting, `None` keeps the default of bundling only.
+    pub transforms: Option<Vec<Transform>>,
+    /// Level of lint rules, overriding their defaults.
+    pub lint: HashMap<String, Level>,
+    /// Compile and run commands by file extension, replacing the built-in ones.
+    pub languages: HashMap<String, Language>,
+    /// Copied into every problem directory by `init` when no `--template` is given.
+    pub template: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct BundleConfig {
+    /// Searched for quoted includes after the directory of the including file.
+    pub include_paths: Vec<PathBuf>,
+    /// Drop comments and blank lines from inlined headers.
+    pub strip_comments: bool,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+fn locate() -> Option<PathBuf> {
+    let local = PathBuf::from(FILE);
+    if local.is_file() {
+        return Some(local);
+    }
+    let home = PathBuf::from(std::env::var_os("HOME")?).join(format!(".{}", FILE));
+    home.is_file().then_some(home)
+}
+
+/// Relative paths in the config are relative to the file, `~/` to the home directory.
+fn resolve(base: &Path, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    base.join(path)
+}
+
+fn load() -> Config {
+    let Some(path) = locate() else {
+        return Config::default();
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) => {
+            output::warning(&format!("Ignoring {}: {}", path.display(), err));
+            return Config::default();
+        }
+    };
+    let mut config: Config = match serde_json::from_str(&content) {
+        Ok(config) => config,
+        Err(err) => {
+            output::warning(&format!("Ignoring {}: {}", path.display(), err));
+            return Config::default();
+        }
+    };
+    let base = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    for include in &mut config.bundle.include_paths {
+        *include = resolve(&base, include);
+    }
+    if let Some(template) = &mut config.template {
+        *template = resolve(&base, template);
+    }
+    config
+}
+
+/// Per-user directory for the history and the in-flight submissions, so every problem directory
+/// shares them.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("submitter"))
+        .unwrap_or_else(|| PathBuf::from(".submitter"))
+}
+
+/// Per-user directory for files edited by hand besides the config, like the site data override.
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("submitter"))
+        .unwrap_or_else(|| PathBuf::from(".submitter"))
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(load)
+}
@@ -0,0 +1,194 @@
+use crate::output;
+use crossterm::style::Color;
+
+/// Unchanged lines shown around every change.
+const CONTEXT: usize = 2;
+/// Longer changed regions are not aligned, all their lines are shown as changed.
+const MAX_LINES: usize = 2000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Byte offset of the first difference, `None` when the texts are identical.
+pub fn first_difference(expected: &str, actual: &str) -> Option<usize> {
+    if expected == actual {
+        return None;
+    }
+    let common = expected
+        .bytes()
+        .zip(actual.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some(common)
+}
+
+/// Line diff, line endings belong to the lines so CRLF and a missing final newline show up.
+fn changes<'a>(expected: &'a str, actual: &'a str) -> Vec<(Kind, &'a str)> {
+    let a: Vec<_> = expected.split_inclusive('\n').collect();
+    let b: Vec<_> = actual.split_inclusive('\n').collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let middle_a = &a[prefix..a.len() - suffix];
+    let middle_b = &b[prefix..b.len() - suffix];
+    let mut result: Vec<_> = a[..prefix].iter().map(|line| (Kind::Same, *line)).collect();
+    if middle_a.len().max(middle_b.len()) <= MAX_LINES {
+        // table[i][j] is the longest common subsequence of middle_a[i..] and middle_b[j..].
+        let mut table = vec![vec![0u32; middle_b.len() + 1]; middle_a.len() + 1];
+        for i in (0..middle_a.len()).rev() {
+            for j in (0..middle_b.len()).rev() {
+                table[i][j] = if middle_a[i] == middle_b[j] {
+                    table[i + 1][j + 1] + 1
+                } else {
+                    table[i + 1][j].max(table[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < middle_a.len() || j < middle_b.len() {
+            if i < middle_a.len() && j < middle_b.len() && middle_a[i] == middle_b[j] {
+                result.push((Kind::Same, middle_a[i]));
+                i += 1;
+                j += 1;
+            } else if i < middle_a.len()
+                && (j == middle_b.len() || table[i + 1][j] >= table[i][j + 1])
+            {
+                result.push((Kind::Removed, middle_a[i]));
+                i += 1;
+            } else {
+                result.push((Kind::Added, middle_b[j]));
+                j += 1;
+            }
+        }
+    } else {
+        result.extend(middle_a.iter().map(|line| (Kind::Removed, *line)));
+        result.extend(middle_b.iter().map(|line| (Kind::Added, *line)));
+    }
+    result.extend(a[a.len() - suffix..].iter().map(|line| (Kind::Same, *line)));
+    result
+}
+
+/// Makes tabs, carriage returns and other control characters visible.
+fn visible(line: &str) -> String {
+    let (body, newline) = match line.strip_suffix('\n') {
+        Some(body) => (body, true),
+        None => (line, false),
+    };
+    let mut result = String::new();
+    for c in body.chars() {
+        match c {
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    if !newline {
+        result.push_str(" (no newline at end)");
+    }
+    result
+}
+
+/// Prints the changed lines of `actual` against `expected` with a little context.
+pub fn print(expected: &str, actual: &str) {
+    let changes = changes(expected, actual);
+    let near_change = |index: usize| {
+        let from = index.saturating_sub(CONTEXT);
+        let to = (index + CONTEXT + 1).min(changes.len());
+        changes[from..to]
+            .iter()
+            .any(|(kind, _)| *kind != Kind::Same)
+    };
+    let mut lines = Vec::new();
+    let mut skipped = false;
+    for (index, (kind, line)) in changes.iter().enumerate() {
+        if !near_change(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            lines.push(("...".to_string(), None));
+            skipped = false;
+        }
+        let line = visible(line);
+        lines.push(match kind {
+            Kind::Same => (format!("  {}", line), None),
+            Kind::Removed => (format!("- {}", line), Some(Color::Red)),
+            Kind::Added => (format!("+ {}", line), Some(Color::Green)),
+        });
+    }
+    if skipped {
+        lines.push(("...".to_string(), None));
+    }
+    if output::json() {
+        let text: Vec<_> = lines.into_iter().map(|(line, _)| line).collect();
+        output::message(&text.join("\n"));
+        return;
+    }
+    for (line, color) in lines {
+        match color {
+            Some(color) => output::text(&output::paint(&line, color)),
+            None => output::text(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_difference_is_a_byte_offset() {
+        assert_eq!(first_difference("1 2\n", "1 2\n"), None);
+        assert_eq!(first_difference("1 2\n", "1 3\n"), Some(2));
+        assert_eq!(first_difference("1 2", "1 2\n"), Some(3));
+        assert_eq!(first_difference("1 2\n", "1 2\r\n"), Some(3));
+    }
+
+    #[test]
+    fn changes_align_the_lines_in_between() {
+        assert_eq!(
+            changes("a\nb\nc\nd\n", "a\nc\nx\nd\n"),
+            vec![
+                (Kind::Same, "a\n"),
+                (Kind::Removed, "b\n"),
+                (Kind::Same, "c\n"),
+                (Kind::Added, "x\n"),
+                (Kind::Same, "d\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_keep_line_endings() {
+        assert_eq!(
+            changes("1\n2\n", "1\n2"),
+            vec![
+                (Kind::Same, "1\n"),
+                (Kind::Removed, "2\n"),
+                (Kind::Added, "2")
+            ]
+        );
+        assert_eq!(
+            changes("1\n", "1\r\n"),
+            vec![(Kind::Removed, "1\n"), (Kind::Added, "1\r\n")]
+        );
+    }
+
+    #[test]
+    fn changes_of_identical_texts_are_all_same() {
+        let text = "1\n2\n3\n";
+        assert!(changes(text, text)
+            .iter()
+            .all(|(kind, _)| *kind == Kind::Same));
+        assert!(changes("", "").is_empty());
+    }
+}
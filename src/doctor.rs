@@ -0,0 +1,232 @@
+use crate::output::{self, CheckState};
+use crate::Site;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use thirtyfour::prelude::*;
+use which::which;
+
+pub async fn run() {
+    match which("docker") {
+        Ok(path) => output::check(CheckState::Ok, "docker", &path.display().to_string()),
+        Err(_) => output::check(
+            CheckState::Fail,
+            "docker",
+            "not found in PATH, install it from https://docs.docker.com/get-docker/",
+        ),
+    }
+    check_docker_daemon();
+    check_shm();
+    let driver = check_webdriver().await;
+    if let Some(driver) = &driver {
+        check_browser(driver).await;
+    }
+    let cookies = check_session_store();
+    match &driver {
+        Some(driver) => {
+            // The mock judge is only ever logged into for testing.
+            for site in Site::all()
+                .into_iter()
+                .filter(|site| !matches!(site, Site::MockJudge(_)))
+            {
+                check_session(driver, &site, &cookies).await;
+            }
+        }
+        None => output::check(
+            CheckState::Warn,
+            "sessions",
+            "skipped, no WebDriver to check them with",
+        ),
+    }
+    if let Some(driver) = driver {
+        let _ = driver.quit().await;
+    }
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        output::check(CheckState::Ok, "terminal", "interactive");
+    } else {
+        output::check(
+            CheckState::Warn,
+            "terminal",
+            "not a TTY, login prompts will fail, log in once from an interactive terminal",
+        );
+    }
+}
+
+fn check_docker_daemon() {
+    let output = Command::new("docker")
+        .args(["info", "--format", "{{.ServerVersion}}"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => output::check(
+            CheckState::Ok,
+            "docker daemon",
+            &format!(
+                "running, version {}",
+                String::from_utf8_lossy(&output.stdout).trim()
+            ),
+        ),
+        Ok(_) => output::check(
+            CheckState::Fail,
+            "docker daemon",
+            "not reachable, start Docker and make sure your user may access it",
+        ),
+        Err(_) => output::check(CheckState::Fail, "docker daemon", "cannot run docker"),
+    }
+}
+
+fn check_shm() {
+    let shm = Path::new("/dev/shm");
+    if !shm.is_dir() {
+        output::check(
+            CheckState::Warn,
+            "/dev/shm",
+            "missing on this host, chrome in docker may crash on large pages",
+        );
+        return;
+    }
+    let probe = shm.join("submitter-doctor");
+    if std::fs::write(&probe, b"").is_err() {
+        output::check(CheckState::Fail, "/dev/shm", "not writable on this host");
+        return;
+    }
+    let _ = std::fs::remove_file(probe);
+    let output = Command::new("docker")
+        .args([
+            "inspect",
+            "selenium-server",
+            "--format",
+            "{{range .Mounts}}{{.Destination}} {{end}}",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            if String::from_utf8_lossy(&output.stdout).contains("/dev/shm") {
+                output::check(CheckState::Ok, "/dev/shm", "mounted into selenium-server");
+            } else {
+                output::check(
+                    CheckState::Fail,
+                    "/dev/shm",
+                    "not mounted into selenium-server, run `docker rm -f selenium-server` so it is restarted with the mount",
+                );
+            }
+        }
+        _ => output::check(
+            CheckState::Ok,
+            "/dev/shm",
+            "writable, selenium-server container is not running",
+        ),
+    }
+}
+
+async fn check_webdriver() -> Option<WebDriver> {
+    for url in ["http://localhost:4444/wd/hub", "http://localhost:4444"] {
+        let caps = DesiredCapabilities::chrome();
+        if let Ok(Ok(driver)) =
+            tokio::time::timeout(Duration::from_secs(20), WebDriver::new(url, caps)).await
+        {
+            output::check(
+                CheckState::Ok,
+                "webdriver",
+                &format!("session started at {}", url),
+            );
+            return Some(driver);
+        }
+    }
+    output::check(
+        CheckState::Fail,
+        "webdriver",
+        "nothing answers on localhost:4444, run any submit to start selenium-server or check `docker logs selenium-server`",
+    );
+    None
+}
+
+async fn check_browser(driver: &WebDriver) {
+    let agent = driver
+        .execute("return navigator.userAgent;", Vec::new())
+        .await
+        .ok()
+        .and_then(|agent| agent.json().as_str().map(|agent| agent.to_string()));
+    let version = agent.as_ref().and_then(|agent| {
+        Regex::new(r"(?:Headless)?Chrome/(\S+)")
+            .unwrap()
+            .captures(agent)
+            .map(|caps| caps[1].to_string())
+    });
+    match (version, agent) {
+        (Some(version), _) => {
+            output::check(CheckState::Ok, "browser", &format!("Chrome {}", version))
+        }
+        (None, Some(agent)) => output::check(
+            CheckState::Warn,
+            "browser",
+            &format!(
+                "not Chrome, site drivers are only tested with Chrome: {}",
+                agent
+            ),
+        ),
+        (None, None) => output::check(
+            CheckState::Fail,
+            "browser",
+            "cannot run scripts in the browser",
+        ),
+    }
+}
+
+fn check_session_store() -> HashMap<String, Vec<Cookie>> {
+    let path = crate::cookies_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        output::check(
+            CheckState::Warn,
+            "session store",
+            &format!("no {}, you will be asked to log in", path.display()),
+        );
+        return Default::default();
+    };
+    match serde_json::from_str::<HashMap<String, Vec<Cookie>>>(&content) {
+        Ok(cookies) => {
+            output::check(
+                CheckState::Ok,
+                "session store",
+                &format!(
+                    "{} has sessions for {} site(s)",
+                    path.display(),
+                    cookies.len()
+                ),
+            );
+            cookies
+        }
+        Err(err) => {
+            output::check(
+                CheckState::Fail,
+                "session store",
+                &format!(
+                    "{} is unreadable ({}), delete it and log in again",
+                    path.display(),
+                    err
+                ),
+            );
+            Default::default()
+        }
+    }
+}
+
+async fn check_session(driver: &WebDriver, site: &Site, cookies: &HashMap<String, Vec<Cookie>>) {
+    let check = format!("{} session", site.name());
+    let cookies = cookies.get(&site.domain()).cloned().unwrap_or_default();
+    if cookies.is_empty() {
+        output::check(CheckState::Warn, &check, "not logged in");
+        return;
+    }
+    match site.restore(driver, cookies).await {
+        Ok(true) => output::check(CheckState::Ok, &check, "valid"),
+        Ok(false) => output::check(
+            CheckState::Fail,
+            &check,
+            "expired, you will be asked to log in on the next submit",
+        ),
+        Err(err) => output::check(CheckState::Fail, &check, &format!("cannot check: {}", err)),
+    }
+}
@@ -0,0 +1,214 @@
+use crate::checker::Checker;
+use crate::output;
+use crate::server;
+use crate::sitedata;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::WebDriver;
+
+/// Written next to the samples.
+pub const METADATA: &str = "problem.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct Problem {
+    pub url: String,
+    pub site: String,
+    pub title: String,
+    pub time_limit_ms: Option<u64>,
+    pub memory_limit_mb: Option<u64>,
+    pub samples: usize,
+    /// How `test` compares outputs, kept when fetching the problem again.
+    #[serde(default)]
+    pub checker: Checker,
+    /// Used by `submit` when no language is given, set by `init` and kept like the checker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+fn number(value: &str) -> f64 {
+    value.replace(',', ".").parse().unwrap_or_default()
+}
+
+/// First duration in `text`, like `2 seconds`, `1.00s` or `500 ms`.
+fn parse_time(text: &str) -> Option<u64> {
+    let regex =
+        Regex::new(r"(?i)(\d+(?:[.,]\d+)?)\s*(ms|millisecond\w*|sec\w*|секунд\w*|s)\b").unwrap();
+    let caps = regex.captures(text)?;
+    let value = number(&caps[1]);
+    let millis = if caps[2].to_lowercase().starts_with('m') {
+        value
+    } else {
+        value * 1000.0
+    };
+    Some(millis.round() as u64)
+}
+
+/// First size in `text`, like `256 megabytes`, `1024 MiB` or `64Mb`.
+fn parse_memory(text: &str) -> Option<u64> {
+    let regex = Regex::new(
+        r"(?i)(\d+(?:[.,]\d+)?)\s*(kb|kib|kilobytes?|mb|mib|megabytes?|мегабайт\w*|gb|gib|gigabytes?)\b",
+    )
+    .unwrap();
+    let caps = regex.captures(text)?;
+    let value = number(&caps[1]);
+    let megabytes = match caps[2].to_lowercase().chars().next() {
+        Some('k') => value / 1024.0,
+        Some('g') => value * 1024.0,
+        _ => value,
+    };
+    Some(megabytes.round() as u64)
+}
+
+async fn texts(driver: &WebDriver, site: &str, key: &str) -> WebDriverResult<Vec<String>> {
+    let mut texts = Vec::new();
+    for element in driver.find_all(sitedata::by(site, key)).await? {
+        let mut text = element.text().await?;
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        texts.push(text);
+    }
+    Ok(texts)
+}
+
+/// Sample pairs from separate input and output elements, or from `samples` where inputs and
+/// outputs alternate.
+async fn samples(driver: &WebDriver, site: &str) -> WebDriverResult<Vec<(String, String)>> {
+    if sitedata::has_selector(site, "samples") {
+        let texts = texts(driver, site, "samples").await?;
+        return Ok(texts
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect());
+    }
+    let inputs = texts(driver, site, "sample_input").await?;
+    let outputs = texts(driver, site, "sample_output").await?;
+    Ok(inputs.into_iter().zip(outputs).collect())
+}
+
+/// Metadata saved by `run` in `dir`, if any.
+pub fn load(dir: &Path) -> Option<Problem> {
+    let content = std::fs::read_to_string(dir.join(METADATA)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save(dir: &Path, problem: &Problem) -> std::io::Result<()> {
+    std::fs::write(
+        dir.join(METADATA),
+        serde_json::to_string_pretty(problem).unwrap(),
+    )
+}
+
+/// Removes the `<n>.in` and `<n>.out` of an earlier fetch, which may have had more samples.
+fn remove_samples(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let numbered = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.parse::<usize>().is_ok());
+        let sample = path
+            .extension()
+            .is_some_and(|extension| extension == "in" || extension == "out");
+        if numbered && sample {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Saves the samples of the problem at `url` as `1.in`, `1.out`, ... and its limits to
+/// `problem.json` in `dir`, false when they could not be saved, which is already reported.
+pub async fn run(driver: &WebDriver, site: &str, url: &str, dir: &Path) -> WebDriverResult<bool> {
+    crate::goto(driver, &server::browser_url(url)).await?;
+    let mut title = String::new();
+    if sitedata::has_selector(site, "problem_title") {
+        if let Ok(element) = driver.find(sitedata::by(site, "problem_title")).await {
+            title = element.text().await?.trim().to_string();
+        }
+    }
+    if title.is_empty() {
+        title = driver.title().await?;
+    }
+    let samples = samples(driver, site).await?;
+    let limits = if sitedata::has_selector(site, "limits") {
+        texts(driver, site, "limits").await?.join("\n")
+    } else {
+        String::new()
+    };
+    let (checker, language) = load(dir)
+        .map(|problem| (problem.checker, problem.language))
+        .unwrap_or_default();
+    let problem = Problem {
+        url: url.to_string(),
+        site: site.to_string(),
+        title,
+        time_limit_ms: parse_time(&limits),
+        memory_limit_mb: parse_memory(&limits),
+        samples: samples.len(),
+        checker,
+        language,
+    };
+    let saved = std::fs::create_dir_all(dir).and_then(|_| {
+        remove_samples(dir)?;
+        for (index, (input, output)) in samples.iter().enumerate() {
+            std::fs::write(dir.join(format!("{}.in", index + 1)), input)?;
+            std::fs::write(dir.join(format!("{}.out", index + 1)), output)?;
+        }
+        save(dir, &problem)
+    });
+    if let Err(err) = saved {
+        output::error(&format!("Failed to save to {}: {}", dir.display(), err));
+        return Ok(false);
+    }
+    if samples.is_empty() {
+        output::warning("No samples found on the problem page");
+    }
+    let limit = |value: Option<u64>, unit: &str| {
+        value
+            .map(|value| format!("{} {}", value, unit))
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    output::message(&format!("Problem:  {}", problem.title));
+    output::message(&format!(
+        "Limits:   {}, {}",
+        limit(problem.time_limit_ms, "ms"),
+        limit(problem.memory_limit_mb, "MB")
+    ));
+    output::message(&format!(
+        "Saved {} sample(s) to {}",
+        samples.len(),
+        dir.display()
+    ));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_reads_seconds_and_milliseconds() {
+        assert_eq!(parse_time("time limit per test 2 seconds"), Some(2000));
+        assert_eq!(parse_time("Time Limit: 1.5 sec"), Some(1500));
+        assert_eq!(parse_time("500 ms"), Some(500));
+        assert_eq!(parse_time("Ограничение времени 0,5 секунды"), Some(500));
+        assert_eq!(parse_time("1.00s / 256 MB"), Some(1000));
+        assert_eq!(parse_time("no limits"), None);
+    }
+
+    #[test]
+    fn parse_memory_reads_megabytes() {
+        assert_eq!(
+            parse_memory("memory limit per test 256 megabytes"),
+            Some(256)
+        );
+        assert_eq!(parse_memory("1024 MiB"), Some(1024));
+        assert_eq!(parse_memory("65536 KB"), Some(64));
+        assert_eq!(parse_memory("1 GB"), Some(1024));
+        assert_eq!(parse_memory("Ограничение памяти 64 мегабайта"), Some(64));
+        assert_eq!(parse_memory("2 seconds"), None);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::diff;
+use crate::history;
+use crate::output;
+use crate::server;
+use crate::sitedata::{self, Status};
+use crate::submission;
+use chrono::DateTime;
+use dialoguer::Confirm;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::WebDriver;
+
+const SCREENSHOT: &str = "dry-run.png";
+
+/// What the user is about to submit, shown before the final click.
+struct Target {
+    title: String,
+    language: String,
+    source: String,
+    file: String,
+    /// The editor turns CRLF into LF, so the source is compared with LF line endings.
+    lf_editor: bool,
+    warned: bool,
+}
+
+/// The submit form as the site shows it once it is filled.
+pub struct Form {
+    /// Language label the form shows, `None` on sites that keep the last language without
+    /// showing it.
+    pub language: Option<String>,
+    /// Whether that language is the one that was asked for, unused without a label.
+    pub language_matches: bool,
+    /// Editor contents read back from the page.
+    pub source: String,
+}
+
+static YES: OnceLock<bool> = OnceLock::new();
+static DRY_RUN: OnceLock<bool> = OnceLock::new();
+static TARGET: Mutex<Option<Target>> = Mutex::new(None);
+
+pub fn set_yes(yes: bool) {
+    let _ = YES.set(yes);
+}
+
+pub fn set_dry_run(dry_run: bool) {
+    let _ = DRY_RUN.set(dry_run);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.get() == Some(&true)
+}
+
+/// Warns about resubmitting the same source or an already solved problem, and remembers
+/// the problem title for `ready`.
+pub async fn inspect(
+    driver: &WebDriver,
+    site: &str,
+    url: &str,
+    language: &str,
+    source: &str,
+    file: &str,
+) -> WebDriverResult<()> {
+    let hash = history::hash(source);
+    let records: Vec<_> = history::load()
+        .into_iter()
+        .filter(|record| record.problem == url)
+        .collect();
+    let mut warned = false;
+    if let Some(record) = records
+        .iter()
+        .rev()
+        .find(|record| record.source_hash == hash)
+    {
+        let date = DateTime::parse_from_rfc3339(&record.submitted_at)
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| record.submitted_at.clone());
+        output::warning(&format!(
+            "This source was already submitted to this problem on {} ({})",
+            date, record.verdict
+        ));
+        warned = true;
+    }
+    crate::goto(driver, &server::browser_url(url)).await?;
+    let accepted_on_judge = sitedata::has_selector(site, "accepted_marker")
+        && driver
+            .find(sitedata::by(site, "accepted_marker"))
+            .await
+            .is_ok();
+    if accepted_on_judge
+        || records
+            .iter()
+            .any(|record| record.status == Status::Accepted)
+    {
+        output::warning("This problem is already accepted");
+        warned = true;
+    }
+    let mut title = String::new();
+    if sitedata::has_selector(site, "problem_title") {
+        if let Ok(element) = driver.find(sitedata::by(site, "problem_title")).await {
+            title = element.text().await?.trim().to_string();
+        }
+    }
+    if title.is_empty() {
+        title = driver.title().await?;
+    }
+    *TARGET.lock().unwrap() = Some(Target {
+        title,
+        language: language.to_string(),
+        source: source.to_string(),
+        file: file.to_string(),
+        lf_editor: sitedata::has_text(site, "editor_line_endings")
+            && sitedata::text(site, "editor_line_endings") == "lf",
+        warned,
+    });
+    Ok(())
+}
+
+/// Called by site drivers right before clicking submit. The editor contents have to match the
+/// source, then the form is reported on a dry run and confirmed otherwise. The submission goes
+/// on only when this returns true.
+pub async fn ready(driver: &WebDriver, form: Form) -> WebDriverResult<bool> {
+    let Some(target) = TARGET.lock().unwrap().take() else {
+        return Ok(!dry_run());
+    };
+    if let Some(language) = &form.language {
+        history::language(language);
+    }
+    if dry_run() {
+        check(driver, &target, &form).await?;
+        return Ok(false);
+    }
+    if !verify(&target, &form) {
+        return Ok(false);
+    }
+    Ok(confirm(&target, form.language.as_deref()))
+}
+
+/// Compares the editor contents with the source byte for byte, printing a diff when they differ.
+/// Sites whose editor is known to turn CRLF into LF compare the source with LF line endings, the
+/// reported offset still being one in the file.
+fn verify(target: &Target, form: &Form) -> bool {
+    let source = if target.lf_editor {
+        target.source.replace("\r\n", "\n")
+    } else {
+        target.source.clone()
+    };
+    let editor = &form.source;
+    let Some(mut offset) = diff::first_difference(&source, editor) else {
+        return true;
+    };
+    if target.lf_editor {
+        offset = file_offset(&target.source, offset);
+    }
+    submission::error(&format!(
+        "Editor contents differ from {} at byte {}, not submitting",
+        target.file, offset
+    ));
+    diff::print(&source, editor);
+    false
+}
+
+/// Offset in `original` of the byte at `offset` once its CRLF line endings became LF.
+fn file_offset(original: &str, offset: usize) -> usize {
+    let bytes = original.as_bytes();
+    let mut normalized = 0;
+    for (index, &byte) in bytes.iter().enumerate() {
+        if normalized == offset {
+            return index;
+        }
+        if !(byte == b'\r' && bytes.get(index + 1) == Some(&b'\n')) {
+            normalized += 1;
+        }
+    }
+    original.len()
+}
+
+async fn check(driver: &WebDriver, target: &Target, form: &Form) -> WebDriverResult<()> {
+    let mut passed = true;
+    let mut unchecked = false;
+    output::message(&format!("Problem:  {}", target.title));
+    match &form.language {
+        Some(language) if form.language_matches => {
+            output::message(&format!("Language: {}", language));
+        }
+        Some(language) => {
+            submission::error(&format!(
+                "Language: {} does not match {}",
+                language, target.language
+            ));
+            passed = false;
+        }
+        None => {
+            output::warning(&format!(
+                "Language: not shown by the site, {} is not checked",
+                target.language
+            ));
+            unchecked = true;
+        }
+    }
+    if verify(target, form) {
+        output::message(&format!("Source:   {} matches the editor", target.file));
+    } else {
+        passed = false;
+    }
+    driver.screenshot(Path::new(SCREENSHOT)).await?;
+    output::message(&format!("Filled form saved to {}", SCREENSHOT));
+    if passed && unchecked {
+        output::warning("Dry run passed without checking the language, nothing was submitted");
+    } else if passed {
+        output::message("Dry run passed, nothing was submitted");
+    } else {
+        submission::error("Dry run failed, nothing was submitted");
+    }
+    Ok(())
+}
+
+fn confirm(target: &Target, language: Option<&str>) -> bool {
+    if YES.get() == Some(&true) {
+        return true;
+    }
+    output::message(&format!("Problem:  {}", target.title));
+    output::message(&format!(
+        "Language: {}",
+        language.unwrap_or("the last one used on the site")
+    ));
+    output::message(&format!("File:     {}", target.file));
+    let answer = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Submit?")
+        .default(!target.warned)
+        .interact_on_opt(&output::term());
+    match answer {
+        Ok(Some(true)) => true,
+        Ok(_) => {
+            submission::error("Submission cancelled");
+            false
+        }
+        Err(_) => {
+            submission::error("Cannot ask for confirmation here, pass --yes to submit anyway");
+            false
+        }
+    }
+}
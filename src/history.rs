@@ -0,0 +1,274 @@
+use crate::config;
+use crate::output::{self, Details};
+use crate::sitedata::Status;
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const FILE: &str = "history.jsonl";
+
+/// One finished submission, a line of the history file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Record {
+    pub submitted_at: String,
+    pub site: String,
+    pub problem: String,
+    pub contest: Option<String>,
+    pub language: String,
+    /// Language label the submit form showed, the same for every spelling of the language. Unset
+    /// on sites that do not show it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_label: Option<String>,
+    pub source_hash: String,
+    pub id: Option<String>,
+    pub url: Option<String>,
+    pub verdict: String,
+    pub status: Status,
+    #[serde(flatten)]
+    pub details: Details,
+}
+
+#[derive(Default)]
+pub struct Filter {
+    pub site: Option<String>,
+    pub contest: Option<String>,
+    pub verdict: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+pub enum Export {
+    Table,
+    Csv,
+    Markdown,
+}
+
+/// Submission of this process, written once its verdict is known.
+static PENDING: Mutex<Option<Record>> = Mutex::new(None);
+
+pub fn hash(source: &str) -> String {
+    Sha256::digest(source.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn contest_of(url: &str) -> Option<String> {
+    let regex = Regex::new(r"/(?:contest|contests|gym)/([^/?#]+)").unwrap();
+    regex.captures(url).map(|caps| caps[1].to_string())
+}
+
+pub fn start(site: &str, problem: &str, language: &str, source: &str) {
+    *PENDING.lock().unwrap() = Some(Record {
+        submitted_at: Local::now().to_rfc3339(),
+        site: site.to_string(),
+        problem: problem.to_string(),
+        contest: contest_of(problem),
+        language: language.to_string(),
+        language_label: None,
+        source_hash: hash(source),
+        id: None,
+        url: None,
+        verdict: String::new(),
+        status: Status::Waiting,
+        details: Details::default(),
+    });
+}
+
+/// Remembers the language the site resolved the requested one to.
+pub fn language(label: &str) {
+    if let Some(record) = PENDING.lock().unwrap().as_mut() {
+        record.language_label = Some(label.to_string());
+    }
+}
+
+pub fn submitted(id: &str, url: &str) {
+    if let Some(record) = PENDING.lock().unwrap().as_mut() {
+        record.id = Some(id.to_string());
+        record.url = Some(url.to_string());
+    }
+}
+
+/// Appends the pending submission with its final verdict.
+pub fn finish(verdict: &str, status: Status, details: &Details) {
+    let Some(mut record) = PENDING.lock().unwrap().take() else {
+        return;
+    };
+    record.verdict = verdict.to_string();
+    record.status = status;
+    record.details = details.clone();
+    append(&record);
+}
+
+/// Errors only end up in the history once the site has accepted the submission.
+pub fn error(message: &str) {
+    let mut pending = PENDING.lock().unwrap();
+    if pending.as_ref().is_some_and(|record| record.url.is_some()) {
+        let mut record = pending.take().unwrap();
+        record.verdict = message.to_string();
+        record.status = Status::Rejected;
+        append(&record);
+    }
+}
+
+fn path() -> PathBuf {
+    config::data_dir().join(FILE)
+}
+
+fn append(record: &Record) {
+    if crate::record::replaying() {
+        return;
+    }
+    let path = path();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path);
+    match file {
+        Ok(mut file) => {
+            let _ = writeln!(file, "{}", serde_json::to_string(record).unwrap());
+        }
+        Err(err) => output::error(&format!("Failed to write {}: {}", path.display(), err)),
+    }
+}
+
+pub fn load() -> Vec<Record> {
+    let Ok(content) = std::fs::read_to_string(path()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn date(record: &Record) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(&record.submitted_at)
+        .ok()
+        .map(|date| date.with_timezone(&Local))
+}
+
+impl Filter {
+    /// `verdict` matches the status (`accepted`, `rejected`) or a part of the verdict text.
+    pub fn matches(&self, record: &Record) -> bool {
+        if self.site.as_ref().is_some_and(|site| *site != record.site) {
+            return false;
+        }
+        if self
+            .contest
+            .as_ref()
+            .is_some_and(|contest| Some(contest) != record.contest.as_ref())
+        {
+            return false;
+        }
+        if let Some(verdict) = &self.verdict {
+            let verdict = verdict.to_lowercase();
+            let status = serde_json::to_value(record.status).unwrap();
+            if status.as_str() != Some(verdict.as_str())
+                && !record.verdict.to_lowercase().contains(&verdict)
+            {
+                return false;
+            }
+        }
+        let day = date(record).map(|date| date.date_naive());
+        if self
+            .since
+            .is_some_and(|since| day.is_none_or(|day| day < since))
+        {
+            return false;
+        }
+        if self
+            .until
+            .is_some_and(|until| day.is_none_or(|day| day > until))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+const COLUMNS: [&str; 9] = [
+    "Date", "Site", "Problem", "Language", "Verdict", "Time", "Memory", "Score", "Url",
+];
+
+fn row(record: &Record) -> [String; 9] {
+    let date = date(record)
+        .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| record.submitted_at.clone());
+    [
+        date,
+        record.site.clone(),
+        record.problem.clone(),
+        record.language.clone(),
+        record.verdict.clone(),
+        record.details.time.clone().unwrap_or_default(),
+        record.details.memory.clone().unwrap_or_default(),
+        record.details.score.clone().unwrap_or_default(),
+        record.url.clone().unwrap_or_default(),
+    ]
+}
+
+fn csv(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn run(filter: &Filter, export: Export) {
+    let records: Vec<_> = load()
+        .into_iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+    if output::json() {
+        for record in &records {
+            println!("{}", serde_json::to_string(record).unwrap());
+        }
+        return;
+    }
+    match export {
+        Export::Table => {
+            if records.is_empty() {
+                println!("No submissions");
+            }
+            for record in &records {
+                let row = row(record);
+                println!(
+                    "{}  {:10} {:50} {:10} {}",
+                    row[0],
+                    row[1],
+                    row[2],
+                    row[3],
+                    output::paint(&record.verdict, output::color(record.status))
+                );
+            }
+        }
+        Export::Csv => {
+            println!("{}", COLUMNS.join(","));
+            for record in &records {
+                let row = row(record).map(|value| csv(&value));
+                println!("{}", row.join(","));
+            }
+        }
+        Export::Markdown => {
+            println!("| {} |", COLUMNS.join(" | "));
+            println!("|{}", "---|".repeat(COLUMNS.len()));
+            for record in &records {
+                let row = row(record).map(|value| value.replace('|', "\\|"));
+                println!("| {} |", row.join(" | "));
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::config;
+use crate::sitedata::Status;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DIR: &str = "inflight";
+/// Finished and interrupted submissions stay listed this long.
+const KEEP: Duration = Duration::from_secs(600);
+
+/// State of a submission made by a running submitter, one file per process.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entry {
+    pub pid: u32,
+    pub site: String,
+    pub problem: String,
+    pub language: String,
+    pub url: Option<String>,
+    pub verdict: String,
+    pub status: Status,
+    pub started: u64,
+    pub finished: Option<u64>,
+}
+
+static CURRENT: Mutex<Option<Entry>> = Mutex::new(None);
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn dir() -> PathBuf {
+    config::data_dir().join(DIR)
+}
+
+fn path(pid: u32) -> PathBuf {
+    dir().join(format!("{}.json", pid))
+}
+
+pub fn start(site: &str, problem: &str, language: &str) {
+    let entry = Entry {
+        pid: std::process::id(),
+        site: site.to_string(),
+        problem: problem.to_string(),
+        language: language.to_string(),
+        url: None,
+        verdict: "Submitting".to_string(),
+        status: Status::Waiting,
+        started: now(),
+        finished: None,
+    };
+    save(&entry);
+    *CURRENT.lock().unwrap() = Some(entry);
+}
+
+/// Applies `change` to the submission of this process, if any.
+pub fn update(change: impl FnOnce(&mut Entry)) {
+    let mut current = CURRENT.lock().unwrap();
+    if let Some(entry) = current.as_mut() {
+        change(entry);
+        save(entry);
+    }
+}
+
+/// Like `update`, but only touches the file when `changed` says so.
+pub fn update_if(changed: impl FnOnce(&Entry) -> bool, change: impl FnOnce(&mut Entry)) {
+    let mut current = CURRENT.lock().unwrap();
+    if let Some(entry) = current.as_mut().filter(|entry| changed(entry)) {
+        change(entry);
+        save(entry);
+    }
+}
+
+fn save(entry: &Entry) {
+    if std::fs::create_dir_all(dir()).is_err() {
+        return;
+    }
+    let _ = std::fs::write(path(entry.pid), serde_json::to_string(entry).unwrap());
+}
+
+/// Signal 0 only checks that the process exists, `EPERM` meaning it runs as another user.
+#[cfg(unix)]
+fn alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    pid > 0
+        && (unsafe { libc::kill(pid, 0) } == 0
+            || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+/// Other systems cannot tell, so entries stay as they were last written.
+#[cfg(not(unix))]
+fn alive(_pid: u32) -> bool {
+    true
+}
+
+/// Every known submission, oldest first, dropping ones that ended a while ago.
+pub fn load() -> Vec<Entry> {
+    let Ok(files) = std::fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for file in files.flatten() {
+        let Ok(content) = std::fs::read_to_string(file.path()) else {
+            continue;
+        };
+        let Ok(mut entry) = serde_json::from_str::<Entry>(&content) else {
+            continue;
+        };
+        if entry.finished.is_none() && !alive(entry.pid) {
+            entry.verdict = "Interrupted".to_string();
+            entry.status = Status::Rejected;
+        }
+        let age = file
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .unwrap_or_default();
+        let ended = entry.finished.is_some() || !alive(entry.pid);
+        if ended && age > KEEP {
+            let _ = std::fs::remove_file(file.path());
+            continue;
+        }
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry| entry.started);
+    entries
+}
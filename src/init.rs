@@ -0,0 +1,171 @@
+use crate::fetch;
+use crate::output;
+use crate::server;
+use crate::sitedata;
+use std::path::Path;
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::WebDriver;
+
+/// Link texts up to this long, like `A` or `B1`, name the problem directories.
+const SHORT_LABEL: usize = 8;
+
+/// `href` of a link on the page at `base` as an absolute url. Resolved here rather than by the
+/// browser, which may reach local judges under another host.
+fn absolute(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    if let Some(href) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, href);
+    }
+    let host = rest.split('/').next().unwrap_or(rest);
+    if href.starts_with('/') {
+        return format!("{}://{}{}", scheme, host, href);
+    }
+    let path = base.split(['?', '#']).next().unwrap_or(base);
+    match path.rfind('/') {
+        Some(end) if end > scheme.len() + 2 => format!("{}/{}", &path[..end], href),
+        _ => format!("{}://{}/{}", scheme, host, href),
+    }
+}
+
+/// Short link texts are problem letters, otherwise the last part of the url names the problem.
+fn label(text: &str, url: &str) -> String {
+    let text = text.trim();
+    let label =
+        if !text.is_empty() && text.len() <= SHORT_LABEL && !text.contains(char::is_whitespace) {
+            text
+        } else {
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            path.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(path)
+        };
+    label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Problems of the contest at `url` as labels and problem urls, in the order of the site.
+async fn problems(
+    driver: &WebDriver,
+    site: &str,
+    url: &str,
+) -> WebDriverResult<Vec<(String, String)>> {
+    let contest = url.trim_end_matches('/');
+    let page = if sitedata::has_url(site, "contest_problems") {
+        sitedata::url_with(site, "contest_problems", &[("contest", contest)])
+    } else {
+        url.to_string()
+    };
+    crate::goto(driver, &server::browser_url(&page)).await?;
+    let mut problems: Vec<(String, String)> = Vec::new();
+    for link in driver
+        .find_all(sitedata::by(site, "contest_problems"))
+        .await?
+    {
+        let Some(href) = link.attr("href").await? else {
+            continue;
+        };
+        let url = absolute(&page, &href);
+        if problems.iter().any(|(_, known)| *known == url) {
+            continue;
+        }
+        problems.push((label(&link.text().await?, &url), url));
+    }
+    Ok(problems)
+}
+
+/// Creates a directory per problem of the contest at `url` in `root`, with the samples and
+/// metadata from `fetch`, a copy of `template` and the language to submit in.
+pub async fn run(
+    driver: &WebDriver,
+    site: &str,
+    url: &str,
+    root: &Path,
+    template: Option<&Path>,
+    language: Option<&str>,
+) -> WebDriverResult<()> {
+    if !sitedata::has_selector(site, "contest_problems") {
+        output::error(&format!(
+            "Listing contest problems is not supported on {}",
+            site
+        ));
+        return Ok(());
+    }
+    output::message("Listing problems");
+    let problems = problems(driver, site, url).await?;
+    if problems.is_empty() {
+        output::error("No problems found on the contest page");
+        return Ok(());
+    }
+    let mut failed = 0;
+    for (label, url) in &problems {
+        let dir = root.join(label);
+        output::text(&format!("{}: {}", label, url));
+        // One problem failing to load leaves the others worth setting up.
+        match fetch::run(driver, site, url, &dir).await {
+            Ok(true) => {}
+            // Already reported.
+            Ok(false) => {
+                failed += 1;
+                continue;
+            }
+            Err(err) => {
+                output::error(&format!("Failed to fetch {}: {}", label, err));
+                failed += 1;
+                continue;
+            }
+        }
+        let Some(mut problem) = fetch::load(&dir) else {
+            failed += 1;
+            continue;
+        };
+        if let Some(language) = language {
+            problem.language = Some(language.to_string());
+            if let Err(err) = fetch::save(&dir, &problem) {
+                output::error(&format!("Failed to save to {}: {}", dir.display(), err));
+                failed += 1;
+                continue;
+            }
+        }
+        let Some(template) = template else {
+            continue;
+        };
+        let target = dir.join(template.file_name().unwrap_or_default());
+        // Solutions already started are never overwritten.
+        if target.exists() {
+            continue;
+        }
+        if let Err(err) = std::fs::copy(template, &target) {
+            output::error(&format!(
+                "Failed to copy {} to {}: {}",
+                template.display(),
+                target.display(),
+                err
+            ));
+            failed += 1;
+        }
+    }
+    output::message(&format!(
+        "Created {} problem directories in {}",
+        problems.len() - failed,
+        root.display()
+    ));
+    if failed > 0 {
+        output::warning(&format!(
+            "{} problem(s) failed to set up, retry them with fetch and --dir",
+            failed
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use crate::checker::{self, Verdict};
+use crate::limits::{self, Finished, Limits};
+use crate::tester::{self, Build, Execution, Outcome, Report};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+
+/// Where the interactor writes its result, as testlib expects an output file.
+const RESULT: &str = ".submitter/interactor.out";
+/// Lines of the exchange shown when a sample fails.
+const SHOWN_LINES: usize = 20;
+
+/// Chunks passed between the processes, `true` for the ones written by the solution.
+type Transcript = Arc<Mutex<Vec<(bool, Vec<u8>)>>>;
+
+fn spawn(command: &mut Command) -> std::io::Result<Child> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/// Copies everything from one process to the other, noting it in the transcript.
+async fn pump(
+    mut from: impl AsyncRead + Unpin,
+    mut to: impl AsyncWrite + Unpin,
+    solution: bool,
+    transcript: Transcript,
+) {
+    let mut buffer = [0; 4096];
+    loop {
+        let read = match from.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        transcript
+            .lock()
+            .unwrap()
+            .push((solution, buffer[..read].to_vec()));
+        if to.write_all(&buffer[..read]).await.is_err() || to.flush().await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn read_all(mut from: impl AsyncRead + Unpin) -> Vec<u8> {
+    let mut data = Vec::new();
+    let _ = from.read_to_end(&mut data).await;
+    data
+}
+
+/// The exchange with `>` before lines of the solution and `<` before lines of the interactor.
+fn render(transcript: &[(bool, Vec<u8>)]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(bool, Vec<u8>)> = None;
+    let flush = |(solution, data): (bool, Vec<u8>), lines: &mut Vec<String>| {
+        let marker = if solution { ">" } else { "<" };
+        for line in String::from_utf8_lossy(&data).lines() {
+            lines.push(format!("{} {}", marker, line));
+        }
+    };
+    for (solution, data) in transcript {
+        match &mut pending {
+            Some((side, pending)) if side == solution => pending.extend_from_slice(data),
+            _ => {
+                if let Some(done) = pending.replace((*solution, data.clone())) {
+                    flush(done, &mut lines);
+                }
+            }
+        }
+    }
+    if let Some(done) = pending {
+        flush(done, &mut lines);
+    }
+    lines
+}
+
+/// Runs the solution against the interactor on one input, keeping the exchange in
+/// `.submitter/transcript-<n>.txt`. The solution is waited for like any other run, so its CPU
+/// time and memory decide the verdict before the interactor does.
+pub async fn run(
+    build: &Build,
+    interactor: &Build,
+    input: &Path,
+    number: usize,
+    limits: Limits,
+) -> Report {
+    if let Err(err) = tester::create_parent(Path::new(RESULT)) {
+        return Report::new(Outcome::Failed, Duration::ZERO).message(format!(
+            "Failed to create the directory of {}: {}",
+            RESULT, err
+        ));
+    }
+    let judge = spawn(
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("exec {} \"$@\"", interactor.run))
+            .arg("interactor")
+            .arg(input)
+            .arg(RESULT),
+    );
+    let mut judge = match judge {
+        Ok(judge) => judge,
+        Err(err) => {
+            return Report::new(Outcome::Failed, Duration::ZERO)
+                .message(format!("Failed to start the interactor: {}", err))
+        }
+    };
+    let mut command = std::process::Command::new("sh");
+    command.arg("-c").arg(format!("exec {}", build.run));
+    #[cfg(unix)]
+    unsafe {
+        command.pre_exec(limits::restrict(limits, build.limit_memory))
+    };
+    let solution = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut solution = match solution {
+        Ok(solution) => solution,
+        Err(err) => return Report::new(Outcome::Failed, Duration::ZERO).message(err.to_string()),
+    };
+    let pipes = (
+        ChildStdin::from_std(solution.stdin.take().unwrap()),
+        ChildStdout::from_std(solution.stdout.take().unwrap()),
+        ChildStderr::from_std(solution.stderr.take().unwrap()),
+    );
+    let (stdin, stdout, stderr) = match pipes {
+        (Ok(stdin), Ok(stdout), Ok(stderr)) => (stdin, stdout, stderr),
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            let _ = solution.kill();
+            let _ = solution.wait();
+            return Report::new(Outcome::Failed, Duration::ZERO).message(err.to_string());
+        }
+    };
+    let transcript = Transcript::default();
+    let pumps = [
+        tokio::spawn(pump(
+            stdout,
+            judge.stdin.take().unwrap(),
+            true,
+            transcript.clone(),
+        )),
+        tokio::spawn(pump(
+            judge.stdout.take().unwrap(),
+            stdin,
+            false,
+            transcript.clone(),
+        )),
+    ];
+    let solution_errors = tokio::spawn(read_all(stderr));
+    let judge_errors = tokio::spawn(read_all(judge.stderr.take().unwrap()));
+
+    let finished = limits::finish(solution, limits).await;
+    // Given as long as the solution to finish, it may still be reading the last answer.
+    let judge_status = tokio::time::timeout(limits.wall(), judge.wait()).await;
+    if judge_status.is_err() {
+        let _ = judge.start_kill();
+    }
+    for pump in pumps {
+        let _ = pump.await;
+    }
+    let solution_errors = solution_errors.await.unwrap_or_default();
+    let judge_errors = judge_errors.await.unwrap_or_default();
+    let message = String::from_utf8_lossy(&judge_errors).trim().to_string();
+
+    let (status, usage, timed_out) = match finished {
+        Ok(finished) => finished,
+        Err(err) => return Report::new(Outcome::Failed, Duration::ZERO).message(err.to_string()),
+    };
+    let execution = tester::ending(
+        build,
+        limits,
+        Finished {
+            status,
+            stdout: Vec::new(),
+            stderr: solution_errors,
+            usage,
+            timed_out,
+        },
+    );
+    let mut report = Report::new(Outcome::Accepted, usage.cpu);
+    // Unknown where the resource usage of processes cannot be read.
+    report.memory_kb = (usage.peak_kb > 0).then_some(usage.peak_kb);
+    match (execution, judge_status) {
+        (Execution::TimedOut, _) => report.outcome = Outcome::TimeLimit,
+        (Execution::MemoryLimit, _) => report.outcome = Outcome::MemoryLimit,
+        (Execution::Crashed(crash), _) => {
+            report.outcome = Outcome::RuntimeError;
+            report = report.message(crash);
+            if !message.is_empty() {
+                report = report.message(format!("Interactor: {}", message));
+            }
+        }
+        (Execution::Finished(_), Err(_)) => {
+            report.outcome = Outcome::Failed;
+            report = report.message("Interactor did not finish after the solution".to_string());
+        }
+        (Execution::Finished(_), Ok(Err(err))) => {
+            report.outcome = Outcome::Failed;
+            report = report.message(err.to_string());
+        }
+        (Execution::Finished(_), Ok(Ok(judge_status))) => {
+            match checker::testlib_verdict("Interactor", judge_status, message) {
+                Verdict::Accepted => {}
+                Verdict::WrongAnswer(reason) => {
+                    report.outcome = Outcome::WrongAnswer;
+                    if let Some(reason) = reason {
+                        report = report.message(reason);
+                    }
+                }
+                Verdict::Failed(message) => {
+                    report.outcome = Outcome::Failed;
+                    report = report.message(message);
+                }
+            }
+        }
+    }
+
+    let lines = render(&transcript.lock().unwrap());
+    let path = PathBuf::from(format!(".submitter/transcript-{}.txt", number));
+    let saved =
+        tester::create_parent(&path).and_then(|_| std::fs::write(&path, lines.join("\n") + "\n"));
+    if !matches!(report.outcome, Outcome::Accepted) {
+        let skipped = lines.len().saturating_sub(SHOWN_LINES);
+        let mut shown = lines[skipped..].join("\n");
+        if skipped > 0 {
+            shown = format!("...\n{}", shown);
+        }
+        if !shown.is_empty() {
+            report = report.message(shown);
+        }
+        if saved.is_ok() {
+            report = report.message(format!("Transcript saved to {}", path.display()));
+        }
+    }
+    report
+}
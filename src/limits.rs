@@ -0,0 +1,277 @@
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::Duration;
+
+/// What a program may use, as the judge would allow.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /// CPU time, with twice as much wall time for programs waiting on input or the system.
+    pub time: Duration,
+    /// Megabytes of address space.
+    pub memory_mb: Option<u64>,
+}
+
+/// What a finished program used.
+#[derive(Default, Clone, Copy)]
+pub struct Usage {
+    /// User and system CPU time.
+    pub cpu: Duration,
+    /// Peak resident set size.
+    pub peak_kb: u64,
+}
+
+pub struct Finished {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub usage: Usage,
+    /// Killed after running out of wall time.
+    pub timed_out: bool,
+}
+
+impl Limits {
+    pub fn wall(&self) -> Duration {
+        self.time * 2 + Duration::from_secs(1)
+    }
+}
+
+/// Sets the rlimits of the child before it starts the program. The CPU limit is rounded up to
+/// whole seconds, so the time used is compared with the limit afterwards as well. Runtimes that
+/// reserve much more address space than they use, like the JVM, are only limited in time.
+#[cfg(unix)]
+pub fn restrict(
+    limits: Limits,
+    address_space: bool,
+) -> impl FnMut() -> io::Result<()> + Send + Sync + 'static {
+    let cpu = limits.time.as_secs_f64().ceil() as libc::rlim_t + 1;
+    let memory = limits
+        .memory_mb
+        .filter(|_| address_space)
+        .map(|mb| mb as libc::rlim_t * 1024 * 1024);
+    move || {
+        let rlimit = |soft, hard| libc::rlimit {
+            rlim_cur: soft,
+            rlim_max: hard,
+        };
+        // Only async-signal-safe calls here, this runs between fork and exec.
+        if unsafe { libc::setrlimit(libc::RLIMIT_CPU, &rlimit(cpu, cpu + 1)) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if let Some(bytes) = memory {
+            if unsafe { libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes, bytes)) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Judges usually let the stack grow up to the memory limit, this fails quietly
+            // where the hard limit is lower.
+            unsafe { libc::setrlimit(libc::RLIMIT_STACK, &rlimit(bytes, bytes)) };
+        }
+        Ok(())
+    }
+}
+
+/// `ru_maxrss` is in bytes on macOS and in kilobytes on Linux and the BSDs.
+#[cfg(target_os = "macos")]
+fn peak_kb(maxrss: u64) -> u64 {
+    maxrss / 1024
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn peak_kb(maxrss: u64) -> u64 {
+    maxrss
+}
+
+#[cfg(unix)]
+fn wait(pid: libc::pid_t) -> io::Result<(ExitStatus, Usage)> {
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } == pid {
+            break;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+    let time = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+    let usage = Usage {
+        cpu: time(usage.ru_utime) + time(usage.ru_stime),
+        peak_kb: peak_kb(usage.ru_maxrss as u64),
+    };
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+fn read_all(child: &mut Child) -> [tokio::task::JoinHandle<Vec<u8>>; 2] {
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    [
+        tokio::task::spawn_blocking(move || {
+            let mut data = Vec::new();
+            let _ = stdout.read_to_end(&mut data);
+            data
+        }),
+        tokio::task::spawn_blocking(move || {
+            let mut data = Vec::new();
+            let _ = stderr.read_to_end(&mut data);
+            data
+        }),
+    ]
+}
+
+/// Runs `command` within `limits`, collecting its output and what it used.
+pub async fn run(
+    command: &mut Command,
+    limits: Limits,
+    address_space: bool,
+) -> io::Result<Finished> {
+    #[cfg(unix)]
+    unsafe {
+        command.pre_exec(restrict(limits, address_space))
+    };
+    #[cfg(not(unix))]
+    let _ = address_space;
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let [stdout, stderr] = read_all(&mut child);
+    let (status, usage, timed_out) = finish(child, limits).await?;
+    Ok(Finished {
+        status,
+        stdout: stdout.await.unwrap_or_default(),
+        stderr: stderr.await.unwrap_or_default(),
+        usage,
+        timed_out,
+    })
+}
+
+/// Waits for the child, killing it after the wall time.
+#[cfg(unix)]
+pub async fn finish(child: Child, limits: Limits) -> io::Result<(ExitStatus, Usage, bool)> {
+    let pid = child.id() as libc::pid_t;
+    // Reaped here rather than by `Child` to get the resource usage of exactly this process.
+    let mut waiter = tokio::task::spawn_blocking(move || wait(pid));
+    let (result, timed_out) = match tokio::time::timeout(limits.wall(), &mut waiter).await {
+        Ok(result) => (result, false),
+        Err(_) => {
+            unsafe { libc::kill(pid, libc::SIGKILL) };
+            (waiter.await, true)
+        }
+    };
+    let (status, usage) = result.map_err(io::Error::other)??;
+    Ok((status, usage, timed_out))
+}
+
+/// Without rlimits and rusage only the wall time is limited, and stands in for the CPU time.
+#[cfg(not(unix))]
+pub async fn finish(mut child: Child, limits: Limits) -> io::Result<(ExitStatus, Usage, bool)> {
+    let started = std::time::Instant::now();
+    let usage = |started: std::time::Instant| Usage {
+        cpu: started.elapsed(),
+        peak_kb: 0,
+    };
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, usage(started), false));
+        }
+        if started.elapsed() > limits.wall() {
+            child.kill()?;
+            return Ok((child.wait()?, usage(started), true));
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Killed for running out of CPU time.
+#[cfg(unix)]
+pub fn cpu_exceeded(status: ExitStatus) -> bool {
+    status.signal() == Some(libc::SIGXCPU)
+}
+
+#[cfg(not(unix))]
+pub fn cpu_exceeded(_status: ExitStatus) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+pub fn describe(status: ExitStatus) -> String {
+    status.to_string()
+}
+
+/// How the program ended, with the signal named when it was killed.
+#[cfg(unix)]
+pub fn describe(status: ExitStatus) -> String {
+    let Some(signal) = status.signal() else {
+        return status.to_string();
+    };
+    let name = match signal {
+        libc::SIGSEGV => {
+            "SIGSEGV, segmentation fault, often an out of bounds access or a stack overflow"
+        }
+        libc::SIGABRT => "SIGABRT, aborted, often a failed assertion or an uncaught exception",
+        libc::SIGFPE => "SIGFPE, arithmetic error, often a division by zero",
+        libc::SIGBUS => "SIGBUS, bus error",
+        libc::SIGILL => "SIGILL, illegal instruction",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGXCPU => "SIGXCPU, CPU time limit exceeded",
+        libc::SIGPIPE => "SIGPIPE, broken pipe",
+        libc::SIGTERM => "SIGTERM",
+        _ => return format!("Killed by signal {}", signal),
+    };
+    format!("Killed by {}", name)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_exceeded_only_on_sigxcpu() {
+        assert!(cpu_exceeded(ExitStatus::from_raw(libc::SIGXCPU)));
+        assert!(!cpu_exceeded(ExitStatus::from_raw(libc::SIGKILL)));
+        assert!(!cpu_exceeded(ExitStatus::from_raw(0)));
+    }
+
+    #[test]
+    fn describe_names_signals() {
+        assert_eq!(
+            describe(ExitStatus::from_raw(libc::SIGFPE)),
+            "Killed by SIGFPE, arithmetic error, often a division by zero"
+        );
+        assert_eq!(describe(ExitStatus::from_raw(64)), "Killed by signal 64");
+        assert_eq!(describe(ExitStatus::from_raw(1 << 8)), "exit status: 1");
+    }
+
+    #[test]
+    fn wall_time_allows_waiting() {
+        let limits = Limits {
+            time: Duration::from_secs(2),
+            memory_mb: None,
+        };
+        assert_eq!(limits.wall(), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn run_reports_output_and_time_limit() {
+        let limits = Limits {
+            time: Duration::from_millis(200),
+            memory_mb: None,
+        };
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("echo out; echo err >&2");
+        let finished = run(&mut command, limits, false).await.unwrap();
+        assert!(finished.status.success() && !finished.timed_out);
+        assert_eq!(
+            (finished.stdout, finished.stderr),
+            (b"out\n".to_vec(), b"err\n".to_vec())
+        );
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let finished = run(&mut command, limits, false).await.unwrap();
+        assert!(finished.timed_out);
+    }
+}
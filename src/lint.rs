@@ -0,0 +1,200 @@
+use crate::bundle::Bundle;
+use crate::config;
+use crate::output;
+use crate::sitedata;
+use crate::transform::Transformed;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Off,
+    Warn,
+    Block,
+}
+
+/// Rule names with their level when `submitter.json` does not set one.
+const RULES: [(&str, Level); 5] = [
+    ("debug_output", Level::Warn),
+    ("freopen", Level::Block),
+    ("java_class", Level::Block),
+    ("source_limit", Level::Block),
+    ("python2_print", Level::Block),
+];
+
+struct Finding {
+    rule: &'static str,
+    message: String,
+}
+
+/// Lines matching `pattern`, skipping the ones that are comments as a whole.
+fn lines_matching(source: &str, pattern: &str, comment: &str) -> Vec<usize> {
+    let regex = Regex::new(pattern).unwrap();
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with(comment) && regex.is_match(line))
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+/// Lines of a bundled source are named by the file and line they come from.
+fn on_lines(lines: &[usize], bundle: Option<&Bundle>) -> String {
+    let shown: Vec<_> = lines
+        .iter()
+        .take(5)
+        .map(
+            |&line| match bundle.and_then(|bundle| bundle.origin(line)) {
+                Some(origin) => format!("{}:{}", origin.file.display(), origin.line),
+                None => line.to_string(),
+            },
+        )
+        .collect();
+    let more = if lines.len() > 5 { ", ..." } else { "" };
+    format!("on line {}{}", shown.join(", "), more)
+}
+
+/// Python 2 is named explicitly by the sites, a `2` alone is also in versions like 3.12.
+fn python2(language: &str) -> bool {
+    Regex::new(r"(?i)\b(python|pypy)\s*2")
+        .unwrap()
+        .is_match(language)
+}
+
+fn check(
+    file: &Path,
+    site: &str,
+    language: &str,
+    source: &str,
+    bundle: Option<&Bundle>,
+) -> Vec<Finding> {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut findings = Vec::new();
+    let mut find = |rule, lines: Vec<usize>, what: &str| {
+        if !lines.is_empty() {
+            findings.push(Finding {
+                rule,
+                message: format!("{} {}", what, on_lines(&lines, bundle)),
+            });
+        }
+    };
+    match extension.as_str() {
+        "c" | "cc" | "cpp" | "cxx" => {
+            find(
+                "debug_output",
+                lines_matching(source, r"\b(cerr|clog)\b", "//"),
+                "Debug output to cerr",
+            );
+            find(
+                "freopen",
+                lines_matching(source, r"\bfreopen\s*\(", "//"),
+                "freopen",
+            );
+        }
+        "rs" => find(
+            "debug_output",
+            lines_matching(source, r"\b(dbg|eprintln|eprint)!", "//"),
+            "Debug output",
+        ),
+        "java" | "kt" => {
+            find(
+                "debug_output",
+                lines_matching(source, r"\bSystem\.err\b", "//"),
+                "Debug output to System.err",
+            );
+            if extension == "java" && sitedata::has_text(site, "java_class") {
+                let class = sitedata::text(site, "java_class");
+                let declaration = Regex::new(r"\bpublic\s+(?:final\s+)?class\s+(\w+)").unwrap();
+                if let Some(caps) = declaration.captures(source) {
+                    if &caps[1] != class {
+                        findings.push(Finding {
+                            rule: "java_class",
+                            message: format!("Class {} has to be named {}", &caps[1], class),
+                        });
+                    }
+                }
+            }
+        }
+        "py" if !python2(language) => find(
+            "python2_print",
+            lines_matching(source, r"^\s*print\s+[^\s(=]", "#"),
+            "Python 2 print statement",
+        ),
+        _ => {}
+    }
+    if sitedata::has_index(site, "source_limit") {
+        let limit = sitedata::index(site, "source_limit");
+        if source.len() > limit {
+            findings.push(Finding {
+                rule: "source_limit",
+                message: format!(
+                    "Source is {} bytes, {} accepts at most {}",
+                    source.len(),
+                    site,
+                    limit
+                ),
+            });
+        }
+    }
+    findings
+}
+
+/// Reports problems with the final source, false if one of them should stop the submission.
+pub fn run(
+    file: &Path,
+    site: &str,
+    language: &str,
+    transformed: &Transformed,
+    force: bool,
+) -> bool {
+    let levels = &config::get().lint;
+    let mut blocked = false;
+    let findings = check(
+        file,
+        site,
+        language,
+        &transformed.source,
+        transformed.bundle.as_ref(),
+    );
+    for finding in findings {
+        let default = RULES
+            .iter()
+            .find(|(rule, _)| *rule == finding.rule)
+            .map(|(_, level)| *level)
+            .unwrap_or(Level::Warn);
+        let level = levels.get(finding.rule).copied().unwrap_or(default);
+        let message = format!("{}: {}", finding.rule, finding.message);
+        match level {
+            Level::Off => {}
+            Level::Warn => output::warning(&message),
+            Level::Block if force => output::warning(&message),
+            Level::Block => {
+                output::error(&message);
+                blocked = true;
+            }
+        }
+    }
+    if blocked {
+        output::error("Not submitting, fix the problems above or pass --force");
+    }
+    !blocked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn python2_is_named_explicitly() {
+        assert!(python2("Python 2.7"));
+        assert!(python2("PyPy2 7.3"));
+        assert!(!python2("Python 3.12"));
+        assert!(!python2("PyPy 3.10 (7.3.12)"));
+    }
+}
@@ -1,23 +1,30 @@
 #![allow(dead_code)]
 
-use crate::{clear, save_source};
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::save_source;
+use crate::sitedata::{self, Status};
+use crate::submission;
 use dialoguer::{Input, Password};
 use std::collections::BTreeSet;
 use std::path::Path;
 use thirtyfour::error::{WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://www.luogu.com.cn/auth/login").await?;
+const SITE: &str = "luogu";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://www.luogu.com.cn/auth/login").await?;
-    if driver.current_url().await?.as_str() != "https://www.luogu.com.cn/auth/login" {
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
+    Ok(driver.current_url().await?.as_str() != crate::site_url(&sitedata::url(SITE, "login")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
     let inputs = driver.find_all(By::Tag("input")).await?;
@@ -31,26 +38,26 @@ pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<
     }
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your luogo login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your luogo password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let captcha: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter the captcha from captcha.png")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     for input in inputs {
         if let Some(placeholder) = input.attr("placeholder").await? {
             match placeholder.as_str() {
-                "用户名、手机号或电子邮箱" => {
+                placeholder if placeholder == sitedata::text(SITE, "login_placeholder") => {
                     input.send_keys(&login).await?;
                 }
-                "密码" => {
+                placeholder if placeholder == sitedata::text(SITE, "password_placeholder") => {
                     input.send_keys(&password).await?;
                 }
-                "右侧图形验证码" => {
+                placeholder if placeholder == sitedata::text(SITE, "captcha_placeholder") => {
                     input.send_keys(&captcha).await?;
                 }
                 _ => {}
@@ -58,7 +65,7 @@ pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<
         }
     }
     driver
-        .find(By::ClassName("btn-login"))
+        .find(sitedata::by(SITE, "login_button"))
         .await?
         .click()
         .await?;
@@ -72,18 +79,34 @@ pub async fn submit(
     _language: String,
     source: String,
 ) -> WebDriverResult<()> {
-    println!("Cannot change language on luogo, language of last submit would be used");
-    driver.goto(&url).await?;
-    driver.find(By::ClassName("solid")).await?.click().await?;
+    output::message("Cannot change language on luogo, language of last submit would be used");
+    crate::goto(driver, &url).await?;
+    driver
+        .find(sitedata::by(SITE, "open_editor"))
+        .await?
+        .click()
+        .await?;
     driver
         .execute(
-            "document.getElementsByClassName('cm-content')[0].innerHTML = arguments[0];",
-            vec![serde_json::to_value(source).unwrap()],
+            "document.querySelector(arguments[1]).innerHTML = arguments[0];",
+            vec![
+                serde_json::to_value(source).unwrap(),
+                serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap(),
+            ],
         )
         .await?;
+    let form = guard::Form {
+        // The editor keeps the language of the last submit and does not show it.
+        language: None,
+        language_matches: false,
+        source: editor_source(driver).await?,
+    };
+    if !guard::ready(driver, form).await? {
+        return Ok(());
+    }
     let buttons = driver.find_all(By::Tag("button")).await?;
     for button in buttons {
-        if button.text().await? == "提交评测" {
+        if button.text().await? == sitedata::text(SITE, "submit_button") {
             button.click().await?;
             break;
         }
@@ -91,17 +114,42 @@ pub async fn submit(
     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
     driver.screenshot(&Path::new("screenshot.png")).await?;
     save_source(driver).await?;
-    eprintln!("Url: {}", driver.current_url().await?);
-    let mut url_printed = false;
+    output::message(&format!("Url: {}", driver.current_url().await?));
+    follow(driver, true).await
+}
+
+/// Contents of the CodeMirror editor, from its state when the page exposes it.
+async fn editor_source(driver: &WebDriver) -> WebDriverResult<String> {
+    let value = driver
+        .execute(
+            "var e = document.querySelector(arguments[0]);\
+            var view = e.cmView && e.cmView.view;\
+            return view ? view.state.doc.toString() : e.innerText;",
+            vec![serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap()],
+        )
+        .await?;
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    if !url.starts_with(&sitedata::url(SITE, "record")) {
+        submission::error("Bad submission url");
+        return Ok(());
+    }
+    crate::goto(driver, &url).await?;
+    follow(driver, false).await
+}
+
+/// Polls the record page, printing its url first once the site redirects to it.
+async fn follow(driver: &WebDriver, print_url: bool) -> WebDriverResult<()> {
+    let mut url_printed = !print_url;
     let mut last_verdict = "".to_string();
     let mut tries = 0;
     loop {
         if !url_printed {
             let url = driver.current_url().await?.to_string();
-            if url.starts_with("https://www.luogu.com.cn/record/") {
-                clear(last_verdict.len());
-                last_verdict = String::new();
-                println!("Submission url {}", url);
+            if url.starts_with(&crate::site_url(&sitedata::url(SITE, "record"))) {
+                submission::submitted(SITE, &url);
                 url_printed = true;
             }
         }
@@ -112,7 +160,7 @@ pub async fn submit(
                     continue;
                 }
                 _ => {
-                    println!("Error while checking verdict");
+                    submission::error("Error while checking verdict");
                     break;
                 }
             },
@@ -127,7 +175,7 @@ async fn iteration(
     last_verdict: &mut String,
     tries: &mut usize,
 ) -> WebDriverResult<bool> {
-    if let Ok(content_el) = driver.find(By::Id("swal2-content")).await {
+    if let Ok(content_el) = driver.find(sitedata::by(SITE, "dialog")).await {
         let content = content_el.inner_html().await?.trim().to_string();
         if content.is_empty() {
             *tries += 1;
@@ -142,28 +190,37 @@ async fn iteration(
             content_el.screenshot(&Path::new("captcha.png")).await?;
             let captcha: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
                 .with_prompt("Enter the captcha from captcha.png")
-                .interact_on(&Term::stdout())
+                .interact_on(&output::term())
                 .unwrap();
-            let input = driver.find(By::ClassName("swal2-input")).await?;
+            let input = driver.find(sitedata::by(SITE, "dialog_input")).await?;
             input.send_keys(&captcha).await?;
-            let button = driver.find(By::ClassName("swal2-confirm")).await?;
+            let button = driver.find(sitedata::by(SITE, "dialog_confirm")).await?;
             button.click().await?;
             tokio::time::sleep(std::time::Duration::from_secs(3)).await;
             return Ok(false);
         }
-        println!("Error from luogo, probably code is too long: {}", content);
+        submission::error(&format!(
+            "Error from luogo, probably code is too long: {}",
+            content
+        ));
         return Ok(true);
     }
-    let (global_verdict, points) = if let Ok(side) = driver.find(By::ClassName("side")).await {
+    let (global_verdict, points) = if let Ok(side) = driver.find(sitedata::by(SITE, "side")).await {
         let spans = side.find_all(By::Tag("span")).await?;
         let mut global_verdict = None;
         let mut points = None;
+        let status_offset = sitedata::index(SITE, "status_offset");
+        let score_offset = sitedata::index(SITE, "score_offset");
         for i in 0..spans.len() {
-            if spans[i].text().await?.contains("评测状态") && i + 2 < spans.len() {
-                global_verdict = Some(spans[i + 2].text().await?.trim().to_string());
+            let text = spans[i].text().await?;
+            if text.contains(sitedata::text(SITE, "status_label"))
+                && i + status_offset < spans.len()
+            {
+                global_verdict = Some(spans[i + status_offset].text().await?.trim().to_string());
             }
-            if spans[i].text().await?.contains("评测分数") && i + 3 < spans.len() {
-                points = Some(spans[i + 3].text().await?.trim().to_string());
+            if text.contains(sitedata::text(SITE, "score_label")) && i + score_offset < spans.len()
+            {
+                points = Some(spans[i + score_offset].text().await?.trim().to_string());
             }
         }
         (global_verdict, points)
@@ -176,16 +233,15 @@ async fn iteration(
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         return Ok(false);
     };
-    clear(last_verdict.len());
-    if global_verdict == Some("Compile Error".to_string()) {
-        let _ = execute!(std::io::stdout(), SetForegroundColor(Color::Red));
-        println!("Compile Error");
-        let _ = execute!(std::io::stdout(), ResetColor);
+    if global_verdict.as_deref() == Some(sitedata::text(SITE, "compile_error")) {
+        submission::finish("Compile Error", Status::Rejected, Details::default());
         return Ok(true);
     }
-    let mut subtasks = driver.find_all(By::ClassName("test-case-wrap")).await?;
+    let mut subtasks = driver.find_all(sitedata::by(SITE, "subtask")).await?;
     if subtasks.is_empty() {
-        subtasks = driver.find_all(By::ClassName("main")).await?;
+        subtasks = driver
+            .find_all(sitedata::by(SITE, "subtask_fallback"))
+            .await?;
     }
     let mut cards = Vec::new();
     let mut verdicts = BTreeSet::new();
@@ -196,22 +252,24 @@ async fn iteration(
             Ok(h) => h.text().await?.trim().to_string(),
             Err(_) => "All tests".to_string(),
         };
-        let tests = subtask.find_all(By::ClassName("content")).await?;
+        let tests = subtask.find_all(sitedata::by(SITE, "test")).await?;
         let mut cur = Vec::new();
         for test in tests {
             total += 1;
-            if test.find(By::ClassName("spinner")).await.is_ok() {
+            if test.find(sitedata::by(SITE, "spinner")).await.is_ok() {
                 pending += 1;
                 cur.push("Judging".to_string());
             } else {
                 let verdict = test
-                    .find(By::ClassName("status"))
+                    .find(sitedata::by(SITE, "test_status"))
                     .await?
                     .text()
                     .await?
                     .trim()
                     .to_string();
-                if verdict != "AC" && !verdict.is_empty() {
+                if sitedata::verdict(SITE, &verdict) != Some(Status::Accepted)
+                    && !verdict.is_empty()
+                {
                     verdicts.insert(verdict.clone());
                 }
                 if verdict.is_empty() {
@@ -224,8 +282,8 @@ async fn iteration(
         }
         cards.push((name, cur));
     }
-    let (mut verdict, color) = if total == 0 {
-        ("Waiting".to_string(), Color::Yellow)
+    let (mut verdict, status) = if total == 0 {
+        ("Waiting".to_string(), Status::Waiting)
     } else if !verdicts.is_empty() {
         let mut all = String::new();
         for verdict in &verdicts {
@@ -234,44 +292,39 @@ async fn iteration(
             }
             all.push_str(verdict);
         }
-        (all, Color::Red)
+        (all, Status::Rejected)
     } else if pending != 0 {
-        ("Judging".to_string(), Color::Yellow)
+        ("Judging".to_string(), Status::Waiting)
     } else {
-        ("Accepted".to_string(), Color::Green)
+        ("Accepted".to_string(), Status::Accepted)
     };
     if pending != 0 {
         verdict += &format!(" {}/{}", total - pending, total);
     }
-    if let Some(points) = points {
-        verdict += &format!(" ({} pts)", points);
-    }
-    let mut stdout = std::io::stdout();
-    let _ = execute!(stdout, SetForegroundColor(color));
-    print!("{}", verdict);
-    let _ = execute!(stdout, ResetColor);
     if total != 0 && pending == 0 {
-        println!();
+        let details = Details {
+            score: points.map(|points| format!("{} pts", points)),
+            ..Default::default()
+        };
+        submission::finish(&verdict, status, details);
+        output::text("Subtask Test Result");
         let mut id = 1;
         for (name, tests) in cards {
-            println!("{}", name);
             for test in tests {
-                print!("  Test #{}: ", id);
+                let status = match sitedata::verdict(SITE, &test) {
+                    Some(Status::Accepted) => Status::Accepted,
+                    _ => Status::Rejected,
+                };
+                output::test(&name, &format!("#{}", id), &test, status);
                 id += 1;
-                let _ = execute!(
-                    stdout,
-                    SetForegroundColor(if &test == "AC" {
-                        Color::Green
-                    } else {
-                        Color::Red
-                    })
-                );
-                println!("{}", test);
-                let _ = execute!(stdout, ResetColor);
             }
         }
         Ok(true)
     } else {
+        if let Some(points) = points {
+            verdict += &format!(" ({} pts)", points);
+        }
+        submission::verdict(&verdict, status);
         *tries += 1;
         if *last_verdict != verdict {
             *tries = 0;
@@ -1,16 +1,50 @@
+#[allow(clippy::needless_question_mark)]
 mod atcoder;
+mod bundle;
+mod checker;
+#[allow(clippy::needless_question_mark)]
 mod codechef;
+#[allow(clippy::needless_question_mark)]
 mod codeforces;
+mod config;
+mod diff;
+mod doctor;
+mod fetch;
+mod guard;
+mod history;
+mod inflight;
+mod init;
+mod interact;
+mod limits;
+mod lint;
+#[allow(clippy::needless_question_mark, clippy::needless_borrow)]
 mod luogu;
+mod mockjudge;
+mod output;
+mod record;
+mod selftest;
+mod server;
+mod sitedata;
+mod stats;
+mod stress;
+mod submission;
+mod tester;
+#[allow(clippy::needless_question_mark)]
 mod toph;
+mod transform;
+#[allow(clippy::needless_question_mark)]
 mod ucup;
+mod upload;
+mod watch;
+#[allow(clippy::needless_question_mark)]
 mod yandex;
 
+use record::site_url;
 use regex::Regex;
 use std::collections::HashMap;
 use std::env;
 use std::fs::read_to_string;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Duration;
 use thirtyfour::prelude::*;
@@ -18,26 +52,627 @@ use which::which;
 
 #[tokio::main]
 async fn main() -> WebDriverResult<()> {
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 4 {
-        println!("Usage: submitter <url> <language> <file>");
+    let mut args: Vec<_> = env::args().skip(1).collect();
+    // First, so that every other error is reported in the chosen format.
+    match take_option(&mut args, "--format").as_deref() {
+        None | Some("text") => output::set_format(output::Format::Text),
+        Some("json") => output::set_format(output::Format::Json),
+        Some(format) => {
+            output::error(&format!("Unknown format {}, expected text or json", format));
+            return Ok(());
+        }
+    }
+    let record_dir = take_option(&mut args, "--record");
+    let replay_dir = take_option(&mut args, "--replay");
+    guard::set_yes(take_flag(&mut args, "--yes"));
+    guard::set_dry_run(take_flag(&mut args, "--dry-run"));
+    let show_final_source = take_flag(&mut args, "--show-final-source");
+    let force = take_flag(&mut args, "--force");
+    match take_option(&mut args, "--upload").as_deref() {
+        None | Some("auto") => upload::set_mode(upload::Mode::Auto),
+        Some("always") => upload::set_mode(upload::Mode::Always),
+        Some("never") => upload::set_mode(upload::Mode::Never),
+        Some(mode) => {
+            output::error(&format!(
+                "Unknown upload mode {}, expected auto, always or never",
+                mode
+            ));
+            return Ok(());
+        }
+    }
+    match take_option(&mut args, "--color").as_deref() {
+        None | Some("auto") => output::set_color(output::ColorChoice::Auto),
+        Some("always") => output::set_color(output::ColorChoice::Always),
+        Some("never") => output::set_color(output::ColorChoice::Never),
+        Some(color) => {
+            output::error(&format!(
+                "Unknown color mode {}, expected auto, always or never",
+                color
+            ));
+            return Ok(());
+        }
+    }
+    if args.first().map(String::as_str) == Some("submit") {
+        let Some(resolved) = resolve_submit(&args[1..]) else {
+            return Ok(());
+        };
+        args = resolved;
+    }
+    match args.first().map(String::as_str) {
+        Some("sites") => {
+            print!("{}", sitedata::defaults());
+        }
+        Some("mock-judge") => {
+            let port = args
+                .get(1)
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(8080);
+            if let Err(err) = mockjudge::serve(port).await {
+                output::error(&format!("Failed to start mock judge: {}", err));
+            }
+        }
+        Some("doctor") => {
+            doctor::run().await;
+        }
+        Some("watch") => {
+            let ends_at = match take_option(&mut args, "--ends-in") {
+                None => None,
+                Some(value) => match watch::parse_duration(&value) {
+                    Some(secs) => Some(inflight::now() + secs),
+                    None => {
+                        output::error(&format!(
+                            "Bad duration {}, expected minutes or h:mm[:ss]",
+                            value
+                        ));
+                        return Ok(());
+                    }
+                },
+            };
+            if let Err(err) = watch::run(ends_at) {
+                output::error(&format!("Failed to draw dashboard: {}", err));
+            }
+        }
+        Some("history") => {
+            let Some(filter) = take_filter(&mut args) else {
+                return Ok(());
+            };
+            let export = match take_option(&mut args, "--export").as_deref() {
+                None => history::Export::Table,
+                Some("csv") => history::Export::Csv,
+                Some("markdown" | "md") => history::Export::Markdown,
+                Some(export) => {
+                    output::error(&format!(
+                        "Unknown export format {}, expected csv or markdown",
+                        export
+                    ));
+                    return Ok(());
+                }
+            };
+            history::run(&filter, export);
+        }
+        Some("stats") => {
+            let Some(filter) = take_filter(&mut args) else {
+                return Ok(());
+            };
+            stats::run(&filter);
+        }
+        Some("bundle") => {
+            let line = take_option(&mut args, "--line");
+            let Some(file) = args.get(1) else {
+                output::error("Usage: submitter bundle <file> [--line <line>]");
+                return Ok(());
+            };
+            let source = match read_to_string(file) {
+                Ok(source) => source,
+                Err(err) => {
+                    output::error(&format!("Failed to read {}: {}", file, err));
+                    return Ok(());
+                }
+            };
+            let bundle = match bundle::bundle(Path::new(file), &source) {
+                Ok(bundle) => bundle,
+                Err(err) => {
+                    output::error(&err);
+                    return Ok(());
+                }
+            };
+            for warning in &bundle.warnings {
+                output::warning(warning);
+            }
+            match line.map(|line| line.parse::<usize>()) {
+                None => print!("{}", bundle.source),
+                Some(Ok(line)) => match bundle.origin(line) {
+                    Some(origin) => println!("{}:{}", origin.file.display(), origin.line),
+                    None => output::error(&format!("The bundle has {} lines", bundle.lines.len())),
+                },
+                Some(Err(_)) => output::error("Bad line number"),
+            }
+        }
+        Some("fetch") => {
+            let dir = take_option(&mut args, "--dir").unwrap_or_else(|| ".".to_string());
+            let Some(url) = args.get(1) else {
+                output::error("Usage: submitter fetch <problem url> [--dir <dir>]");
+                return Ok(());
+            };
+            let Some(driver) = start(&record_dir, &replay_dir).await? else {
+                return Ok(());
+            };
+            fetch(&driver, url, Path::new(&dir)).await?;
+            driver.quit().await?;
+        }
+        Some("init") => {
+            let dir = take_option(&mut args, "--dir").unwrap_or_else(|| ".".to_string());
+            let template = take_option(&mut args, "--template")
+                .map(PathBuf::from)
+                .or_else(|| config::get().template.clone());
+            let language = take_option(&mut args, "--language");
+            let Some(url) = args.get(1) else {
+                output::error("Usage: submitter init <contest url> [--dir <dir>] [--template <file>] [--language <language>]");
+                return Ok(());
+            };
+            if let Some(template) = &template {
+                if !template.is_file() {
+                    output::error(&format!(
+                        "Failed to read {}: no such file",
+                        template.display()
+                    ));
+                    return Ok(());
+                }
+            }
+            let Some(driver) = start(&record_dir, &replay_dir).await? else {
+                return Ok(());
+            };
+            init(
+                &driver,
+                url,
+                Path::new(&dir),
+                template.as_deref(),
+                language.as_deref(),
+            )
+            .await?;
+            driver.quit().await?;
+        }
+        Some("test") => {
+            let dir = take_option(&mut args, "--dir").unwrap_or_else(|| ".".to_string());
+            let time_limit = take_option(&mut args, "--time-limit");
+            let memory_limit = take_option(&mut args, "--memory-limit");
+            let url = take_option(&mut args, "--url");
+            let language = take_option(&mut args, "--language");
+            let submit_if_pass = take_flag(&mut args, "--submit-if-pass");
+            let checker = take_option(&mut args, "--checker");
+            let interactor = take_option(&mut args, "--interactor");
+            let Some(file) = args.get(1) else {
+                output::error("Usage: submitter test <file> [--dir <dir>] [--time-limit <ms>] [--memory-limit <MB>] [--checker <checker> | --interactor <interactor>] [--submit-if-pass [--language <language>] [--url <url>]]");
+                return Ok(());
+            };
+            let dir = Path::new(&dir);
+            let mut problem = fetch::load(dir);
+            let Some(checker) = choose_checker(checker, dir, &mut problem) else {
+                return Ok(());
+            };
+            let Some(limits) = choose_limits(time_limit, memory_limit, &problem) else {
+                return Ok(());
+            };
+            let language = language.or_else(|| {
+                problem
+                    .as_ref()
+                    .and_then(|problem| problem.language.clone())
+            });
+            let url = url
+                .or_else(|| problem.map(|problem| problem.url))
+                .unwrap_or_default();
+            let Some(transformed) = load_source(file, &url) else {
+                return Ok(());
+            };
+            for warning in &transformed.warnings {
+                output::warning(warning);
+            }
+            let Some(build) = tester::compile(Path::new(file), &transformed).await else {
+                return Ok(());
+            };
+            let passed = match interactor {
+                // A file is built like a checker, anything else is a shell command.
+                Some(interactor) if Path::new(&interactor).is_file() => {
+                    let Some(interactor) = tester::helper(Path::new(&interactor)).await else {
+                        return Ok(());
+                    };
+                    let referee = tester::Referee::Interactor(&interactor);
+                    tester::run(&build, referee, dir, limits).await
+                }
+                Some(interactor) => {
+                    let interactor = tester::Build {
+                        run: interactor,
+                        limit_memory: true,
+                    };
+                    let referee = tester::Referee::Interactor(&interactor);
+                    tester::run(&build, referee, dir, limits).await
+                }
+                None => {
+                    if !matches!(checker, checker::Checker::Lines) {
+                        output::text(&format!("Checker: {}", checker.describe()));
+                    }
+                    let Some(judge) = checker::prepare(checker).await else {
+                        return Ok(());
+                    };
+                    let referee = tester::Referee::Checker(&judge);
+                    tester::run(&build, referee, dir, limits).await
+                }
+            };
+            if !passed || !submit_if_pass {
+                return Ok(());
+            }
+            if url.is_empty() {
+                output::error("No problem URL to submit to, pass --url or fetch the problem first");
+                return Ok(());
+            }
+            let Some(language) = &language else {
+                output::error(
+                    "No language to submit in, pass --language or init the problem with one",
+                );
+                return Ok(());
+            };
+            submit(
+                &record_dir,
+                &replay_dir,
+                &url,
+                language,
+                file,
+                &transformed,
+                force,
+            )
+            .await?;
+        }
+        Some("stress") => {
+            let dir = take_option(&mut args, "--dir").unwrap_or_else(|| ".".to_string());
+            let generator = take_option(&mut args, "--gen");
+            let brute = take_option(&mut args, "--brute");
+            let time_limit = take_option(&mut args, "--time-limit");
+            let memory_limit = take_option(&mut args, "--memory-limit");
+            let checker = take_option(&mut args, "--checker");
+            let (Some(seed), Some(iterations), Some(size)) = (
+                take_number(&mut args, "--seed"),
+                take_number(&mut args, "--iterations"),
+                take_number(&mut args, "--size"),
+            ) else {
+                return Ok(());
+            };
+            let (Some(file), Some(generator), Some(brute)) = (args.get(1), generator, brute) else {
+                output::error("Usage: submitter stress <file> --gen <generator> --brute <brute force> [--seed <seed>] [--iterations <count>] [--size <size>] [--time-limit <ms>] [--memory-limit <MB>] [--checker <checker>] [--dir <dir>]");
+                return Ok(());
+            };
+            let dir = Path::new(&dir);
+            let mut problem = fetch::load(dir);
+            let Some(checker) = choose_checker(checker, dir, &mut problem) else {
+                return Ok(());
+            };
+            let Some(limits) = choose_limits(time_limit, memory_limit, &problem) else {
+                return Ok(());
+            };
+            let url = problem.map(|problem| problem.url).unwrap_or_default();
+            let Some(transformed) = load_source(file, &url) else {
+                return Ok(());
+            };
+            for warning in &transformed.warnings {
+                output::warning(warning);
+            }
+            for helper in [&generator, &brute] {
+                if !Path::new(helper).is_file() {
+                    output::error(&format!("Failed to read {}: no such file", helper));
+                    return Ok(());
+                }
+            }
+            let Some(solution) = tester::compile(Path::new(file), &transformed).await else {
+                return Ok(());
+            };
+            let Some(generator) = tester::helper(Path::new(&generator)).await else {
+                return Ok(());
+            };
+            let Some(brute) = tester::helper(Path::new(&brute)).await else {
+                return Ok(());
+            };
+            let Some(judge) = checker::prepare(checker).await else {
+                return Ok(());
+            };
+            let programs = stress::Programs {
+                solution,
+                brute,
+                generator,
+                judge,
+            };
+            let options = stress::Options {
+                seed: seed.unwrap_or(1),
+                iterations: iterations.unwrap_or(1000),
+                size,
+                limits,
+            };
+            stress::run(&programs, &options, dir).await;
+        }
+        Some("status") if args.len() == 2 => {
+            let Some(driver) = start(&record_dir, &replay_dir).await? else {
+                return Ok(());
+            };
+            status(&driver, &args[1]).await?;
+            driver.quit().await?;
+        }
+        Some("selftest") => {
+            let Some(driver) = start(&record_dir, &replay_dir).await? else {
+                return Ok(());
+            };
+            selftest::run(&driver, args.get(1).map(String::as_str)).await?;
+            driver.quit().await?;
+        }
+        _ if args.len() == 3 => {
+            let url = &args[0];
+            let language = &args[1];
+            let file = &args[2];
+            let Some(transformed) = load_source(file, url) else {
+                return Ok(());
+            };
+            if show_final_source {
+                for warning in &transformed.warnings {
+                    output::warning(warning);
+                }
+                print!("{}", transformed.source);
+                return Ok(());
+            }
+            for warning in &transformed.warnings {
+                output::warning(warning);
+            }
+            submit(
+                &record_dir,
+                &replay_dir,
+                url,
+                language,
+                file,
+                &transformed,
+                force,
+            )
+            .await?;
+        }
+        _ => {
+            println!("Usage: submitter [--record <dir> | --replay <dir>] [--format text|json] [--color auto|always|never] [--yes] [--dry-run] [--upload auto|always|never] [--show-final-source] [--force] <url> <language> <file>");
+            println!("       submitter [--format text|json] status <submission url>");
+            println!("       submitter [--record <dir> | --replay <dir>] selftest [site]");
+            println!("       submitter submit [<language>] <file>");
+            println!("       submitter fetch <problem url> [--dir <dir>]");
+            println!("       submitter init <contest url> [--dir <dir>] [--template <file>] [--language <language>]");
+            println!("       submitter test <file> [--dir <dir>] [--time-limit <ms>] [--memory-limit <MB>] [--checker <checker> | --interactor <interactor>] [--submit-if-pass [--language <language>] [--url <url>]]");
+            println!("       submitter stress <file> --gen <generator> --brute <brute force> [--seed <seed>] [--iterations <count>]");
+            println!("                        [--size <size>] [--time-limit <ms>] [--memory-limit <MB>] [--checker <checker>] [--dir <dir>]");
+            println!("       submitter history [--site <site>] [--contest <contest>] [--verdict <verdict>]");
+            println!("                         [--since <date>] [--until <date>] [--export csv|markdown]");
+            println!("       submitter stats [--site <site>] [--contest <contest>] [--since <date>] [--until <date>]");
+            println!("       submitter watch [--ends-in <minutes | h:mm[:ss]>]");
+            println!("       submitter bundle <file> [--line <line>]");
+            println!("       submitter doctor");
+            println!("       submitter mock-judge [port]");
+            println!("       submitter sites");
+        }
+    }
+    Ok(())
+}
+
+/// Checker from `--checker`, remembered in `problem.json`, or the one saved there before.
+fn choose_checker(
+    value: Option<String>,
+    dir: &Path,
+    problem: &mut Option<fetch::Problem>,
+) -> Option<checker::Checker> {
+    let Some(value) = value else {
+        return Some(
+            problem
+                .as_ref()
+                .map(|problem| problem.checker.clone())
+                .unwrap_or_default(),
+        );
+    };
+    let Some(checker) = checker::parse(&value) else {
+        output::error(&format!(
+            "Unknown checker {}, expected lines, tokens, ignore-case, float[:epsilon] or a checker file",
+            value
+        ));
+        return None;
+    };
+    if let Some(problem) = problem {
+        problem.checker = checker.clone();
+        if let Err(err) = fetch::save(dir, problem) {
+            output::error(&format!(
+                "Failed to save the checker to {}: {}",
+                dir.display(),
+                err
+            ));
+        }
+    }
+    Some(checker)
+}
+
+/// Limits from `--time-limit` and `--memory-limit`, else from `problem.json`. Without either
+/// the time limit is the default and memory is not limited.
+fn choose_limits(
+    time: Option<String>,
+    memory: Option<String>,
+    problem: &Option<fetch::Problem>,
+) -> Option<limits::Limits> {
+    let mut time_ms = problem.as_ref().and_then(|problem| problem.time_limit_ms);
+    let mut memory_mb = problem.as_ref().and_then(|problem| problem.memory_limit_mb);
+    for (value, limit, unit) in [
+        (time, &mut time_ms, "milliseconds"),
+        (memory, &mut memory_mb, "megabytes"),
+    ] {
+        if let Some(value) = value {
+            let Ok(value) = value.parse() else {
+                output::error(&format!("Bad limit {}, expected {}", value, unit));
+                return None;
+            };
+            *limit = Some(value);
+        }
+    }
+    Some(limits::Limits {
+        time: Duration::from_millis(time_ms.unwrap_or(tester::DEFAULT_TIME_LIMIT_MS)),
+        memory_mb,
+    })
+}
+
+/// Source as it is submitted, after the transforms from `submitter.json`.
+fn load_source(file: &str, url: &str) -> Option<transform::Transformed> {
+    let source = match read_to_string(file) {
+        Ok(source) => source,
+        Err(err) => {
+            output::error(&format!("Failed to read {}: {}", file, err));
+            return None;
+        }
+    };
+    let site = domain_of(url).and_then(|domain| Site::from_domain(&domain));
+    match transform::apply(Path::new(file), site.as_ref().map(Site::name), source) {
+        Ok(transformed) => Some(transformed),
+        Err(err) => {
+            output::error(&err);
+            None
+        }
+    }
+}
+
+/// Arguments of `submit [<language>] <file>` as `<url> <language> <file>`, with the problem url
+/// and the default language from `problem.json` in the working directory.
+fn resolve_submit(args: &[String]) -> Option<Vec<String>> {
+    if !matches!(args.len(), 1 | 2) {
+        output::error("Usage: submitter submit [<language>] <file>");
+        return None;
+    }
+    let Some(problem) = fetch::load(Path::new(".")) else {
+        output::error(&format!(
+            "No {} here, run init or fetch first, or pass the problem url",
+            fetch::METADATA
+        ));
+        return None;
+    };
+    let (language, file) = match args {
+        [language, file] => (language.clone(), file.clone()),
+        _ => {
+            let Some(language) = problem.language else {
+                output::error(&format!(
+                    "No language in {}, pass it before the file",
+                    fetch::METADATA
+                ));
+                return None;
+            };
+            (language, args[0].clone())
+        }
+    };
+    Some(vec![problem.url, language, file])
+}
+
+/// Lints the final source and submits it.
+async fn submit(
+    record_dir: &Option<String>,
+    replay_dir: &Option<String>,
+    url: &str,
+    language: &str,
+    file: &str,
+    transformed: &transform::Transformed,
+    force: bool,
+) -> WebDriverResult<()> {
+    if let Some(site) = domain_of(url).and_then(|domain| Site::from_domain(&domain)) {
+        if !lint::run(Path::new(file), site.name(), language, transformed, force) {
+            return Ok(());
+        }
+    }
+    let Some(driver) = start(record_dir, replay_dir).await? else {
         return Ok(());
+    };
+    run(&driver, url, language, &transformed.source, file).await?;
+    driver.quit().await
+}
+
+async fn start(
+    record_dir: &Option<String>,
+    replay_dir: &Option<String>,
+) -> WebDriverResult<Option<WebDriver>> {
+    if let Some(dir) = replay_dir {
+        if let Err(err) = record::replay(dir).await {
+            output::error(&format!("Failed to load recording from {}: {}", dir, err));
+            return Ok(None);
+        }
+    }
+    let Some(driver) = connect().await? else {
+        return Ok(None);
+    };
+    if let Some(dir) = record_dir {
+        if let Err(err) = record::start(dir) {
+            output::error(&format!("Failed to start recording to {}: {}", dir, err));
+            driver.quit().await?;
+            return Ok(None);
+        }
+        record::spawn(driver.clone());
+    }
+    Ok(Some(driver))
+}
+
+/// History filters shared by `history` and `stats`, `None` after reporting a bad option.
+fn take_filter(args: &mut Vec<String>) -> Option<history::Filter> {
+    let mut filter = history::Filter {
+        site: take_option(args, "--site"),
+        contest: take_option(args, "--contest"),
+        verdict: take_option(args, "--verdict"),
+        ..Default::default()
+    };
+    for (option, date) in [
+        ("--since", &mut filter.since),
+        ("--until", &mut filter.until),
+    ] {
+        if let Some(value) = take_option(args, option) {
+            let Some(value) = history::parse_date(&value) else {
+                output::error(&format!("Bad date {}, expected YYYY-MM-DD", value));
+                return None;
+            };
+            *date = Some(value);
+        }
+    }
+    Some(filter)
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let Some(pos) = args.iter().position(|arg| arg == name) else {
+        return false;
+    };
+    args.remove(pos);
+    true
+}
+
+/// Numeric option, `None` after reporting a value that is not a number.
+fn take_number(args: &mut Vec<String>, name: &str) -> Option<Option<u64>> {
+    match take_option(args, name).map(|value| value.parse()) {
+        None => Some(None),
+        Some(Ok(value)) => Some(Some(value)),
+        Some(Err(_)) => {
+            output::error(&format!("Bad {}, expected a number", name));
+            None
+        }
     }
-    let url = &args[1];
-    let language = &args[2];
-    let file = &args[3];
-    let source = read_to_string(file).unwrap();
+}
 
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let pos = args.iter().position(|arg| arg == name)?;
+    if pos + 1 >= args.len() {
+        args.remove(pos);
+        return None;
+    }
+    args.remove(pos);
+    Some(args.remove(pos))
+}
+
+#[allow(clippy::needless_borrows_for_generic_args)]
+async fn connect() -> WebDriverResult<Option<WebDriver>> {
     let caps = DesiredCapabilities::chrome();
 
     let driver = match WebDriver::new("http://localhost:4444/wd/hub", caps.clone()).await {
         Ok(driver) => driver,
         Err(_) => {
             if which("docker").is_err() {
-                println!("Please install docker");
-                return Ok(());
+                output::error("Please install docker");
+                return Ok(None);
             }
-            println!("Selenium is not running, starting");
+            output::message("Selenium is not running, starting");
             let mut command = Command::new("docker");
             command.args(&[
                 "run",
@@ -45,88 +680,178 @@ async fn main() -> WebDriverResult<()> {
                 "-d",
                 "-p",
                 "4444:4444",
+                "--add-host",
+                "host.docker.internal:host-gateway",
                 "--name",
                 "selenium-server",
                 "-v",
                 "//dev/shm:/dev/shm",
                 "selenium/standalone-chrome:latest",
             ]);
+            if output::json() {
+                command.stdout(std::process::Stdio::null());
+            }
             command.status().unwrap();
-            println!("Waiting for selenium to start");
+            output::message("Waiting for selenium to start");
             tokio::time::sleep(Duration::from_secs(5)).await;
             WebDriver::new("http://localhost:4444", caps).await?
         }
     };
-
-    run(&driver, &url, &language, &source).await?;
-
-    driver.quit().await?;
-    Ok(())
+    Ok(Some(driver))
 }
 
 async fn run(
     driver: &WebDriver,
-    url: &String,
-    language: &String,
-    source: &String,
+    url: &str,
+    language: &str,
+    source: &str,
+    file: &str,
 ) -> WebDriverResult<()> {
-    let cookies_string = read_to_string("cookies.json").unwrap_or("{}".to_string());
-    let mut all_cookies: HashMap<String, Vec<Cookie>> =
-        serde_json::from_str(&cookies_string).unwrap_or(HashMap::new());
-    let url_regex = Regex::new(r"https?://(?:www\.)?([^/]+).*").unwrap();
-    let domain = {
-        match url_regex.captures(url) {
-            None => {
-                println!("Unexpected URL");
-                return Ok(());
-            }
-            Some(caps) => caps[1].to_string(),
-        }
+    let Some(domain) = domain_of(url) else {
+        output::error("Unexpected URL");
+        return Ok(());
+    };
+    if domain == "luogu.com.cn" {
+        output::error("Luogu support is discontinued due to captcha");
+        return Ok(());
+    }
+    let Some(site) = Site::from_domain(&domain) else {
+        output::error("Unsupported domain");
+        return Ok(());
+    };
+    if !log_in(driver, &site).await? {
+        return Ok(());
+    }
+    guard::inspect(driver, site.name(), url, language, source, file).await?;
+    upload::set_file(file);
+    if guard::dry_run() {
+        output::message(&format!("Dry run, filling the form for {}", url));
+    } else {
+        submission::start(site.name(), url, language, source);
+    }
+    site.submit(
+        driver,
+        url.to_string(),
+        language.to_string(),
+        source.to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn status(driver: &WebDriver, url: &str) -> WebDriverResult<()> {
+    let Some(domain) = domain_of(url) else {
+        output::error("Unexpected URL");
+        return Ok(());
     };
+    // Records are public, so no login and its captcha are needed.
+    if domain == "luogu.com.cn" {
+        return luogu::status(driver, url.to_string()).await;
+    }
+    let Some(site) = Site::from_domain(&domain) else {
+        output::error("Unsupported domain");
+        return Ok(());
+    };
+    if !log_in(driver, &site).await? {
+        return Ok(());
+    }
+    site.status(driver, url.to_string()).await
+}
 
-    let site = match domain.as_str() {
-        "codeforces.com" => Site::Codeforces,
-        "codechef.com" => Site::Codechef,
-        "contest.yandex.com" => Site::Yandex,
-        "atcoder.jp" => Site::AtCoder,
-        "contest.ucup.ac" => Site::UniversalCup,
-        "luogu.com.cn" => {
-            eprintln!("Luogu support is discontinued due to captcha");
-            return Ok(());
-        }
-        "toph.co" => Site::Toph,
-        _ => {
-            println!("Unsupported domain");
-            return Ok(());
-        }
+async fn fetch(driver: &WebDriver, url: &str, dir: &Path) -> WebDriverResult<()> {
+    let Some(domain) = domain_of(url) else {
+        output::error("Unexpected URL");
+        return Ok(());
+    };
+    // Statements are public, so no login and its captcha are needed.
+    if domain == "luogu.com.cn" {
+        return fetch::run(driver, "luogu", url, dir).await.map(|_| ());
+    }
+    let Some(site) = Site::from_domain(&domain) else {
+        output::error("Unsupported domain");
+        return Ok(());
     };
+    if !log_in(driver, &site).await? {
+        return Ok(());
+    }
+    // A failed save is already reported.
+    fetch::run(driver, site.name(), url, dir).await.map(|_| ())
+}
 
-    println!("Logging in");
+async fn init(
+    driver: &WebDriver,
+    url: &str,
+    dir: &Path,
+    template: Option<&Path>,
+    language: Option<&str>,
+) -> WebDriverResult<()> {
+    let Some(domain) = domain_of(url) else {
+        output::error("Unexpected URL");
+        return Ok(());
+    };
+    let Some(site) = Site::from_domain(&domain) else {
+        output::error("Unsupported domain");
+        return Ok(());
+    };
+    if !log_in(driver, &site).await? {
+        return Ok(());
+    }
+    init::run(driver, site.name(), url, dir, template, language).await
+}
+
+async fn log_in(driver: &WebDriver, site: &Site) -> WebDriverResult<bool> {
+    let mut all_cookies = load_cookies();
+    let domain = site.domain();
+    output::login_started(site.name());
     match site
-        .login(&driver, all_cookies.get(&domain).cloned().unwrap_or(vec![]))
+        .login(driver, all_cookies.get(&domain).cloned().unwrap_or(vec![]))
         .await
     {
         Ok(cookies) => {
+            output::login_finished(site.name(), true);
             all_cookies.insert(domain, cookies.clone());
-            let cookies_string = serde_json::to_string(&all_cookies).unwrap();
-            std::fs::write("cookies.json", cookies_string).unwrap();
+            save_cookies(&all_cookies);
+            Ok(true)
         }
         Err(err) => {
+            output::login_finished(site.name(), false);
             all_cookies.insert(domain, Vec::new());
-            let cookies_string = serde_json::to_string(&all_cookies).unwrap();
-            std::fs::write("cookies.json", cookies_string).unwrap();
-            eprintln!(
+            save_cookies(&all_cookies);
+            output::error(&format!(
                 "Failed to login:\n{}\n{:?}",
                 driver.current_url().await?,
                 err
-            );
-            return Ok(());
+            ));
+            Ok(false)
         }
+    }
+}
+
+fn domain_of(url: &str) -> Option<String> {
+    let url_regex = Regex::new(r"https?://(?:www\.)?([^/]+).*").unwrap();
+    url_regex.captures(url).map(|caps| caps[1].to_string())
+}
+
+/// Sessions of every site, kept next to where submitter is run from.
+fn cookies_path() -> PathBuf {
+    PathBuf::from("cookies.json")
+}
+
+fn load_cookies() -> HashMap<String, Vec<Cookie>> {
+    let cookies_string = if record::replaying() {
+        "{}".to_string()
+    } else {
+        read_to_string(cookies_path()).unwrap_or("{}".to_string())
     };
-    println!("Submitting");
-    site.submit(&driver, url.clone(), language.clone(), source.clone())
-        .await?;
-    Ok(())
+    serde_json::from_str(&cookies_string).unwrap_or(HashMap::new())
+}
+
+fn save_cookies(all_cookies: &HashMap<String, Vec<Cookie>>) {
+    if record::replaying() {
+        return;
+    }
+    let cookies_string = serde_json::to_string(all_cookies).unwrap();
+    std::fs::write(cookies_path(), cookies_string).unwrap();
 }
 
 enum Site {
@@ -137,9 +862,62 @@ enum Site {
     UniversalCup,
     // Luogu,
     Toph,
+    MockJudge(String),
 }
 
 impl Site {
+    fn from_domain(domain: &str) -> Option<Site> {
+        match domain {
+            "codeforces.com" => Some(Site::Codeforces),
+            "codechef.com" => Some(Site::Codechef),
+            "contest.yandex.com" => Some(Site::Yandex),
+            "atcoder.jp" => Some(Site::AtCoder),
+            "contest.ucup.ac" => Some(Site::UniversalCup),
+            "toph.co" => Some(Site::Toph),
+            domain if mockjudge::is_mock(domain) => {
+                Some(Site::MockJudge(format!("http://{}", domain)))
+            }
+            _ => None,
+        }
+    }
+
+    fn all() -> Vec<Site> {
+        vec![
+            Site::Codeforces,
+            Site::Codechef,
+            Site::Yandex,
+            Site::AtCoder,
+            Site::UniversalCup,
+            Site::Toph,
+            Site::MockJudge("http://localhost:8080".to_string()),
+        ]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Site::Codeforces => "codeforces",
+            Site::Codechef => "codechef",
+            Site::Yandex => "yandex",
+            Site::AtCoder => "atcoder",
+            Site::UniversalCup => "ucup",
+            Site::Toph => "toph",
+            Site::MockJudge(_) => "mockjudge",
+        }
+    }
+
+    fn domain(&self) -> String {
+        match self {
+            Site::Codeforces => "codeforces.com".to_string(),
+            Site::Codechef => "codechef.com".to_string(),
+            Site::Yandex => "contest.yandex.com".to_string(),
+            Site::AtCoder => "atcoder.jp".to_string(),
+            Site::UniversalCup => "contest.ucup.ac".to_string(),
+            Site::Toph => "toph.co".to_string(),
+            Site::MockJudge(base) => domain_of(base).unwrap_or_default(),
+        }
+    }
+
+    /// Submits with the site's driver.
     async fn submit(
         &self,
         driver: &WebDriver,
@@ -155,6 +933,31 @@ impl Site {
             Site::UniversalCup => ucup::submit(driver, url, language, source).await,
             // Site::Luogu => luogu::submit(driver, url, language, source).await,
             Site::Toph => toph::submit(driver, url, language, source).await,
+            Site::MockJudge(_) => mockjudge::submit(driver, url, language, source).await,
+        }
+    }
+
+    async fn status(&self, driver: &WebDriver, url: String) -> WebDriverResult<()> {
+        match self {
+            Site::Codeforces => codeforces::status(driver, url).await,
+            Site::Codechef => codechef::status(driver, url).await,
+            Site::Yandex => yandex::status(driver, url).await,
+            Site::AtCoder => atcoder::status(driver, url).await,
+            Site::UniversalCup => ucup::status(driver, url).await,
+            Site::Toph => toph::status(driver, url).await,
+            Site::MockJudge(_) => mockjudge::status(driver, url).await,
+        }
+    }
+
+    async fn restore(&self, driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+        match self {
+            Site::Codeforces => codeforces::restore(driver, cookies).await,
+            Site::Codechef => codechef::restore(driver, cookies).await,
+            Site::Yandex => yandex::restore(driver, cookies).await,
+            Site::AtCoder => atcoder::restore(driver, cookies).await,
+            Site::UniversalCup => ucup::restore(driver, cookies).await,
+            Site::Toph => toph::restore(driver, cookies).await,
+            Site::MockJudge(base) => mockjudge::restore(driver, base, cookies).await,
         }
     }
 
@@ -171,10 +974,16 @@ impl Site {
             Site::UniversalCup => ucup::login(driver, cookies).await,
             // Site::Luogu => luogu::login(driver, cookies).await,
             Site::Toph => toph::login(driver, cookies).await,
+            Site::MockJudge(base) => mockjudge::login(driver, base, cookies).await,
         }
     }
 }
 
+async fn goto(driver: &WebDriver, url: &str) -> WebDriverResult<()> {
+    driver.goto(site_url(url)).await?;
+    record::snapshot(driver, Some(url)).await
+}
+
 async fn select_value(selector: WebElement, value: &str) -> WebDriverResult<bool> {
     selector.focus().await?;
     let mut last = selector.value().await?;
@@ -201,6 +1010,18 @@ async fn select_value(selector: WebElement, value: &str) -> WebDriverResult<bool
     Ok(false)
 }
 
+/// Text of the chosen option of a select, or the shown text of a custom dropdown.
+async fn selected_label(driver: &WebDriver, element: WebElement) -> WebDriverResult<String> {
+    let label = driver
+        .execute(
+            "var e = arguments[0];\
+            return e.options && e.selectedIndex >= 0 ? e.options[e.selectedIndex].text : e.innerText;",
+            vec![element.to_json()?],
+        )
+        .await?;
+    Ok(label.json().as_str().unwrap_or_default().trim().to_string())
+}
+
 async fn set_value(driver: &WebDriver, element: WebElement, value: String) -> WebDriverResult<()> {
     driver
         .execute(
@@ -211,21 +1032,9 @@ async fn set_value(driver: &WebDriver, element: WebElement, value: String) -> We
     Ok(())
 }
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::needless_borrow)]
 async fn save_source(driver: &WebDriver) -> WebDriverResult<()> {
     driver.screenshot(&Path::new("screenshot.png")).await?;
     std::fs::write("source.html", driver.source().await?).unwrap();
     Ok(())
 }
-
-fn clear(len: usize) {
-    for _ in 0..len {
-        print!("{}", 8u8 as char);
-    }
-    for _ in 0..len {
-        print!(" ");
-    }
-    for _ in 0..len {
-        print!("{}", 8u8 as char);
-    }
-}
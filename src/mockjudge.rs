@@ -0,0 +1,505 @@
+use crate::guard;
+use crate::output::{self, Details};
+use crate::server::{self, escape_html, Request, Response};
+use crate::sitedata::{self, Status};
+use crate::submission;
+use dialoguer::{Input, Password};
+use std::sync::Mutex;
+use std::time::Instant;
+use thirtyfour::error::{WebDriverErrorInner, WebDriverResult};
+use thirtyfour::{By, Cookie, WebDriver};
+
+const SITE: &str = "mockjudge";
+
+const PROBLEMS: [(&str, &str); 3] = [
+    ("A", "Sum of Two Numbers"),
+    ("B", "Reverse the String"),
+    ("C", "Shortest Path"),
+];
+
+/// Problem, sample input and sample output.
+const SAMPLES: [(&str, &str, &str); 4] = [
+    ("A", "1 2\n", "3\n"),
+    ("A", "-5 7\n", "2\n"),
+    ("B", "hello\n", "olleh\n"),
+    ("C", "3 2\n1 2 4\n2 3 1\n", "5\n"),
+];
+
+const LANGUAGES: [(&str, &str); 4] = [
+    ("cpp17", "GNU C++17"),
+    ("cpp20", "GNU C++20"),
+    ("python3", "Python 3"),
+    ("rust", "Rust"),
+];
+
+const TESTS: u64 = 5;
+
+struct Submission {
+    id: usize,
+    user: String,
+    problem: String,
+    language: String,
+    source: String,
+    time: Instant,
+}
+
+impl Submission {
+    fn verdict(&self) -> (String, &'static str) {
+        let elapsed = self.time.elapsed().as_secs();
+        if elapsed < 2 {
+            return ("In queue".to_string(), "verdict-waiting");
+        }
+        if self.source.trim().is_empty() {
+            return ("Compilation error".to_string(), "verdict-rejected");
+        }
+        let failed = if self.source.contains("TLE") {
+            Some(("Time limit exceeded", 3))
+        } else if self.source.contains("WA") {
+            Some(("Wrong answer", 2))
+        } else {
+            None
+        };
+        let test = elapsed - 1;
+        match failed {
+            Some((verdict, on)) if test > on => {
+                (format!("{} on test {}", verdict, on), "verdict-rejected")
+            }
+            _ if test <= TESTS => (format!("Running on test {}", test), "verdict-waiting"),
+            _ => ("Accepted".to_string(), "verdict-accepted"),
+        }
+    }
+}
+
+struct Judge {
+    submissions: Vec<Submission>,
+}
+
+pub fn is_mock(domain: &str) -> bool {
+    domain.starts_with("localhost:")
+        || domain.starts_with("127.0.0.1:")
+        || domain.starts_with(&format!("{}:", server::browser_host()))
+}
+
+pub async fn serve(port: u16) -> std::io::Result<()> {
+    let listener = server::bind(port).await?;
+    println!("Mock judge is running on http://localhost:{}", port);
+    println!(
+        "Try: submitter http://localhost:{}/problem/A c++17 solution.cpp",
+        port
+    );
+    let judge = Mutex::new(Judge {
+        submissions: Vec::new(),
+    });
+    server::serve(listener, move |request| {
+        judge.lock().unwrap().handle(request)
+    })
+    .await;
+    Ok(())
+}
+
+impl Judge {
+    fn handle(&mut self, request: Request) -> Response {
+        let user = request
+            .cookie("session")
+            .filter(|user| !user.is_empty() && valid_user(user));
+        let path = request.path.trim_end_matches('/');
+        match (request.method.as_str(), path) {
+            ("GET", "") => page(&user, "Problems", &problem_list()),
+            ("GET", "/login") => match user {
+                Some(_) => Response::redirect("/"),
+                None => page(&user, "Login", LOGIN_FORM),
+            },
+            ("POST", "/login") => {
+                let form = request.form();
+                match form.get("username").filter(|user| !user.is_empty()) {
+                    Some(user) if valid_user(user) => Response::redirect("/")
+                        .with_header("Set-Cookie", &format!("session={}; Path=/", user)),
+                    Some(_) => page(
+                        &None,
+                        "Login",
+                        &format!(
+                            "<span class=\"error\">Use only letters, digits, _ and -</span>{}",
+                            LOGIN_FORM
+                        ),
+                    ),
+                    None => Response::redirect("/login"),
+                }
+            }
+            ("GET", "/logout") => {
+                Response::redirect("/").with_header("Set-Cookie", "session=; Path=/")
+            }
+            ("GET", "/submit") => match user {
+                Some(_) => {
+                    let problem = request.query.get("problem").cloned().unwrap_or_default();
+                    page(&user, "Submit", &submit_form(&problem))
+                }
+                None => Response::redirect("/login"),
+            },
+            ("POST", "/submit") => {
+                let Some(user) = user else {
+                    return Response::redirect("/login");
+                };
+                let form = request.form();
+                let problem = form.get("problem").cloned().unwrap_or_default();
+                let language = form.get("language").cloned().unwrap_or_default();
+                if !PROBLEMS.iter().any(|(id, _)| *id == problem)
+                    || !LANGUAGES.iter().any(|(id, _)| *id == language)
+                {
+                    return page(
+                        &Some(user),
+                        "Submit",
+                        &format!(
+                            "<span class=\"error\">Unknown problem or language</span>{}",
+                            submit_form(&problem)
+                        ),
+                    );
+                }
+                self.submissions.push(Submission {
+                    id: self.submissions.len() + 1,
+                    user,
+                    problem,
+                    language,
+                    source: form.get("source").cloned().unwrap_or_default(),
+                    time: Instant::now(),
+                });
+                Response::redirect("/status")
+            }
+            ("GET", "/status") => {
+                let rows = self
+                    .submissions
+                    .iter()
+                    .rev()
+                    .filter(|submission| Some(&submission.user) == user.as_ref())
+                    .collect::<Vec<_>>();
+                page(&user, "My submissions", &status_table(&rows))
+            }
+            ("GET", path) => {
+                if let Some(id) = path.strip_prefix("/problem/") {
+                    if let Some((id, name)) = PROBLEMS.iter().find(|(problem, _)| *problem == id) {
+                        let solved = self.submissions.iter().any(|submission| {
+                            Some(&submission.user) == user.as_ref()
+                                && submission.problem == *id
+                                && submission.verdict().1 == "verdict-accepted"
+                        });
+                        let solved = if solved {
+                            "<span class=\"solved\">Solved</span> "
+                        } else {
+                            ""
+                        };
+                        return page(
+                            &user,
+                            &format!("Problem {}. {}", id, name),
+                            &format!(
+                                "{}{}<a href=\"/submit?problem={}\">Submit</a>",
+                                solved,
+                                statement(id),
+                                id
+                            ),
+                        );
+                    }
+                }
+                if let Some(id) = path.strip_prefix("/submission/") {
+                    let submission = self
+                        .submissions
+                        .iter()
+                        .find(|submission| submission.id.to_string() == id);
+                    if let Some(submission) = submission {
+                        let title = format!("Submission {}", submission.id);
+                        return page(&user, &title, &status_table(&[submission]));
+                    }
+                }
+                Response::not_found()
+            }
+            _ => Response::not_found(),
+        }
+    }
+}
+
+/// User names go into the session cookie as they are, so nothing that could end the cookie
+/// value or the header is allowed.
+fn valid_user(user: &str) -> bool {
+    user.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+const LOGIN_FORM: &str = "<form method=\"post\" action=\"/login\">\
+    <input id=\"username\" name=\"username\" placeholder=\"Login\">\
+    <input id=\"password\" name=\"password\" type=\"password\" placeholder=\"Password\">\
+    <button id=\"login-submit\" type=\"submit\">Login</button>\
+    </form>";
+
+fn page(user: &Option<String>, title: &str, body: &str) -> Response {
+    let header = match user {
+        Some(user) => format!(
+            "<span class=\"user\">{}</span> <a href=\"/status\">My submissions</a> <a href=\"/logout\">Logout</a>",
+            escape_html(user)
+        ),
+        None => "<a href=\"/login\">Login</a>".to_string(),
+    };
+    Response::html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title} - Mock Judge</title></head>\
+        <body><div class=\"header\"><a href=\"/\">Mock Judge</a> {header}</div>\
+        <h1 class=\"title\">{title}</h1>{body}</body></html>",
+        title = escape_html(title),
+        header = header,
+        body = body
+    ))
+}
+
+fn problem_list() -> String {
+    let mut list = "<ul class=\"problems\">".to_string();
+    for (id, name) in PROBLEMS {
+        list += &format!("<li><a href=\"/problem/{id}\">{id}. {name}</a></li>");
+    }
+    list + "</ul>"
+}
+
+fn statement(problem: &str) -> String {
+    let mut statement =
+        "<div class=\"limits\">Time limit: 1 second, memory limit: 256 megabytes</div>".to_string();
+    for (_, input, output) in SAMPLES.iter().filter(|(id, _, _)| *id == problem) {
+        statement += &format!(
+            "<div class=\"sample\"><pre class=\"input\">{}</pre><pre class=\"output\">{}</pre></div>",
+            escape_html(input),
+            escape_html(output)
+        );
+    }
+    statement
+}
+
+fn submit_form(problem: &str) -> String {
+    let mut form = "<form method=\"post\" action=\"/submit\"><select name=\"problem\">".to_string();
+    for (id, name) in PROBLEMS {
+        let selected = if id == problem { " selected" } else { "" };
+        form += &format!("<option value=\"{id}\"{selected}>{id}. {name}</option>");
+    }
+    form += "</select><select name=\"language\">";
+    for (id, name) in LANGUAGES {
+        form += &format!("<option value=\"{id}\">{name}</option>");
+    }
+    form + "</select><textarea id=\"source\" name=\"source\" rows=\"20\" cols=\"80\"></textarea>\
+        <button id=\"submit\" type=\"submit\">Submit</button></form>"
+}
+
+fn status_table(submissions: &[&Submission]) -> String {
+    let mut table = "<table class=\"status\"><tr><th>#</th><th>Problem</th><th>Language</th>\
+        <th>Verdict</th><th>Time</th><th>Memory</th></tr>"
+        .to_string();
+    for submission in submissions {
+        let (verdict, class) = submission.verdict();
+        let done = class != "verdict-waiting";
+        let language = LANGUAGES
+            .iter()
+            .find(|(id, _)| *id == submission.language)
+            .map(|(_, name)| *name)
+            .unwrap_or("");
+        table += &format!(
+            "<tr><td class=\"id-cell\"><a href=\"/submission/{id}\" submissionid=\"{id}\">{id}</a></td>\
+            <td>{problem}</td><td>{language}</td>\
+            <td class=\"status-cell\"><span class=\"{class}\">{verdict}</span></td>\
+            <td class=\"time-cell\">{time}</td><td class=\"memory-cell\">{memory}</td></tr>",
+            id = submission.id,
+            problem = submission.problem,
+            language = language,
+            class = class,
+            verdict = verdict,
+            time = if done { "15 ms" } else { "" },
+            memory = if done { "1024 KB" } else { "" },
+        );
+    }
+    table + "</table>"
+}
+
+pub async fn restore(
+    driver: &WebDriver,
+    base: &str,
+    cookies: Vec<Cookie>,
+) -> WebDriverResult<bool> {
+    let base = server::browser_url(base);
+    crate::goto(driver, &base).await?;
+    driver.delete_all_cookies().await?;
+    for cookie in cookies {
+        driver.add_cookie(cookie).await?;
+    }
+    let login_url = sitedata::url_with(SITE, "login", &[("base", &base)]);
+    crate::goto(driver, &login_url).await?;
+    Ok(driver.current_url().await?.as_str() != crate::site_url(&login_url))
+}
+
+pub async fn login(
+    driver: &WebDriver,
+    base: &str,
+    cookies: Vec<Cookie>,
+) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, base, cookies).await? {
+        return driver.get_all_cookies().await;
+    }
+    let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Enter your mock judge login (any name)")
+        .interact_on(&output::term())
+        .unwrap();
+    let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Enter your mock judge password (anything)")
+        .allow_empty_password(true)
+        .interact_on(&output::term())
+        .unwrap();
+    driver
+        .find(sitedata::by(SITE, "login"))
+        .await?
+        .send_keys(login)
+        .await?;
+    driver
+        .find(sitedata::by(SITE, "password"))
+        .await?
+        .send_keys(password)
+        .await?;
+    driver
+        .find(sitedata::by(SITE, "login_button"))
+        .await?
+        .click()
+        .await?;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    driver.get_all_cookies().await
+}
+
+pub async fn submit(
+    driver: &WebDriver,
+    url: String,
+    language: String,
+    source: String,
+) -> WebDriverResult<()> {
+    let pos = match url.rfind("/problem/") {
+        None => {
+            submission::error("Bad url");
+            return Ok(());
+        }
+        Some(pos) => pos,
+    };
+    let base = &url[..pos];
+    let id = url[pos + 9..].replace('/', "");
+    crate::goto(
+        driver,
+        &sitedata::url_with(
+            SITE,
+            "submit",
+            &[("base", &server::browser_url(base)), ("problem", &id)],
+        ),
+    )
+    .await?;
+    let problem = driver.find(sitedata::by(SITE, "problem")).await?;
+    if !crate::select_value(problem, &id).await? {
+        submission::error("Bad id");
+        return Ok(());
+    }
+    let language_id = get_language(language);
+    let element = driver.find(sitedata::by(SITE, "language")).await?;
+    if !crate::select_value(element, &language_id).await? {
+        submission::error("Bad language");
+        return Ok(());
+    }
+    let input_field = driver.find(sitedata::by(SITE, "source")).await?;
+    crate::set_value(driver, input_field.clone(), source).await?;
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let form = guard::Form {
+        language: Some(crate::selected_label(driver, language_field.clone()).await?),
+        language_matches: language_field.value().await? == Some(language_id),
+        source: input_field.value().await?.unwrap_or_default(),
+    };
+    if !guard::ready(driver, form).await? {
+        return Ok(());
+    }
+    driver
+        .find(sitedata::by(SITE, "submit_button"))
+        .await?
+        .click()
+        .await?;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    if let Ok(error) = driver.find(sitedata::by(SITE, "error")).await {
+        submission::error(&format!("Error submitting: {}", error.text().await?));
+        return Ok(());
+    }
+    follow(driver, Some(base)).await
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    if !url.contains("/submission/") {
+        submission::error("Bad submission url");
+        return Ok(());
+    }
+    crate::goto(driver, &server::browser_url(&url)).await?;
+    follow(driver, None).await
+}
+
+/// Polls the newest verdict on the page, printing its url first if `base` is given.
+async fn follow(driver: &WebDriver, base: Option<&str>) -> WebDriverResult<()> {
+    let mut last_verdict = "".to_string();
+    let mut printed_url = false;
+    loop {
+        if let Some(base) = base.filter(|_| !printed_url) {
+            if let Ok(id_cell) = driver.find(sitedata::by(SITE, "id_cell")).await {
+                if let Some(id) = id_cell
+                    .find(By::Tag("a"))
+                    .await?
+                    .attr("submissionid")
+                    .await?
+                {
+                    printed_url = true;
+                    submission::submitted(
+                        SITE,
+                        &sitedata::url_with(SITE, "submission", &[("base", base), ("id", &id)]),
+                    );
+                }
+            }
+        }
+        match iteration(driver, &mut last_verdict).await {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(err) => match *err {
+                WebDriverErrorInner::NoSuchElement(_) => {
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                WebDriverErrorInner::StaleElementReference(_) => {
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                _ => {
+                    return Err(err);
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn iteration(driver: &WebDriver, last_verdict: &mut String) -> WebDriverResult<bool> {
+    let cell = driver.find(sitedata::by(SITE, "status_cell")).await?;
+    let span = cell.find(By::Tag("span")).await?;
+    let verdict = span.text().await?;
+    let class = span.class_name().await?.unwrap_or_default();
+    let status = sitedata::verdict(SITE, &class).unwrap_or(Status::Waiting);
+    if status != Status::Waiting {
+        let mut details = Details::default();
+        if let Ok(cell) = driver.find(sitedata::by(SITE, "time_cell")).await {
+            details.time = Some(cell.text().await?);
+        }
+        if let Ok(cell) = driver.find(sitedata::by(SITE, "memory_cell")).await {
+            details.memory = Some(cell.text().await?);
+        }
+        submission::finish(&verdict, status, details);
+        return Ok(true);
+    }
+    submission::verdict(&verdict, status);
+    *last_verdict = verdict;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    driver.refresh().await?;
+    Ok(false)
+}
+
+fn get_language(language: String) -> String {
+    match language.to_lowercase().as_str() {
+        "c++" | "c++17" => "cpp17".to_string(),
+        "c++20" => "cpp20".to_string(),
+        "python" | "python3" => "python3".to_string(),
+        "rust" => "rust".to_string(),
+        _ => language,
+    }
+}
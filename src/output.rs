@@ -0,0 +1,442 @@
+use crate::sitedata::Status;
+use crossterm::cursor::MoveToColumn;
+use crossterm::style::{Color, Stylize};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{execute, queue};
+use dialoguer::console::Term;
+use serde::{Deserialize, Serialize};
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, Once, OnceLock};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// One line of `--format json` output.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    LoginStarted {
+        site: &'a str,
+    },
+    LoginFinished {
+        site: &'a str,
+        success: bool,
+    },
+    Submitting {
+        site: &'a str,
+        url: &'a str,
+        language: &'a str,
+    },
+    Submitted {
+        site: &'a str,
+        id: &'a str,
+        url: &'a str,
+    },
+    Verdict {
+        verdict: &'a str,
+        status: Status,
+    },
+    Test {
+        group: &'a str,
+        test: &'a str,
+        verdict: &'a str,
+        status: Status,
+    },
+    Final {
+        verdict: &'a str,
+        status: Status,
+        #[serde(flatten)]
+        details: &'a Details,
+    },
+    /// Result of one sample or stress test run locally.
+    LocalTest {
+        group: &'a str,
+        test: &'a str,
+        verdict: &'a str,
+        status: Status,
+    },
+    /// Outcome of a local run, like `final` for a submission.
+    LocalResult {
+        verdict: &'a str,
+        status: Status,
+        #[serde(flatten)]
+        details: &'a Details,
+    },
+    /// One line of `doctor`.
+    Check {
+        check: &'a str,
+        state: CheckState,
+        message: &'a str,
+    },
+    Message {
+        text: &'a str,
+    },
+    Warning {
+        message: &'a str,
+    },
+    Error {
+        message: &'a str,
+    },
+}
+
+/// Whatever a site shows next to the final verdict.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Details {
+    pub time: Option<String>,
+    pub memory: Option<String>,
+    pub score: Option<String>,
+}
+
+/// How a `doctor` check went.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckState {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Live status line, redrawn in place on a terminal.
+struct Line {
+    text: String,
+    status: Status,
+    frame: usize,
+    shown: bool,
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+static COLOR: OnceLock<bool> = OnceLock::new();
+static COLOR_STDERR: OnceLock<bool> = OnceLock::new();
+static LINE: Mutex<Line> = Mutex::new(Line {
+    text: String::new(),
+    status: Status::Waiting,
+    frame: 0,
+    shown: false,
+});
+static TICKER: Once = Once::new();
+
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn json() -> bool {
+    FORMAT.get() == Some(&Format::Json)
+}
+
+/// `NO_COLOR` and non-terminal output disable colors unless forced with `--color always`.
+/// Stdout and stderr are decided apart, as either may be redirected.
+pub fn set_color(choice: ColorChoice) {
+    let enabled = |terminal: bool| match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && terminal
+        }
+    };
+    let stdout = enabled(std::io::stdout().is_terminal());
+    let stderr = enabled(std::io::stderr().is_terminal());
+    let _ = COLOR.set(stdout);
+    let _ = COLOR_STDERR.set(stderr);
+    dialoguer::console::set_colors_enabled(stdout);
+    dialoguer::console::set_colors_enabled_stderr(stderr);
+}
+
+fn colors() -> bool {
+    *COLOR.get_or_init(|| std::io::stdout().is_terminal())
+}
+
+fn colors_stderr() -> bool {
+    *COLOR_STDERR.get_or_init(|| std::io::stderr().is_terminal())
+}
+
+/// `paint` for text written to stderr.
+fn paint_stderr(text: &str, color: Color) -> String {
+    if colors_stderr() {
+        text.with(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Spinner and in-place updates only make sense on a terminal.
+fn live() -> bool {
+    !json() && std::io::stdout().is_terminal()
+}
+
+/// Terminal for login prompts, kept off stdout when it carries events.
+pub fn term() -> Term {
+    if json() {
+        Term::stderr()
+    } else {
+        Term::stdout()
+    }
+}
+
+fn emit(event: &Event) {
+    println!("{}", serde_json::to_string(event).unwrap());
+}
+
+pub fn color(status: Status) -> Color {
+    match status {
+        Status::Waiting => Color::Yellow,
+        Status::Accepted => Color::Green,
+        Status::Rejected => Color::Red,
+    }
+}
+
+pub fn paint(text: &str, color: Color) -> String {
+    if colors() {
+        text.with(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn draw(line: &Line) {
+    let mut stdout = std::io::stdout();
+    let _ = queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine));
+    print!(
+        "{} {}",
+        SPINNER[line.frame % SPINNER.len()],
+        paint(&line.text, color(line.status))
+    );
+    let _ = stdout.flush();
+}
+
+fn spawn_ticker() {
+    TICKER.call_once(|| {
+        tokio::spawn(async {
+            loop {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let mut line = LINE.lock().unwrap();
+                if line.shown {
+                    line.frame += 1;
+                    draw(&line);
+                }
+            }
+        });
+    });
+}
+
+fn end_line() {
+    let mut line = LINE.lock().unwrap();
+    if line.shown {
+        let mut stdout = std::io::stdout();
+        let _ = execute!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine));
+    }
+    line.shown = false;
+    line.text.clear();
+}
+
+pub fn login_started(site: &str) {
+    if json() {
+        emit(&Event::LoginStarted { site });
+    } else {
+        println!("Logging in");
+    }
+}
+
+pub fn login_finished(site: &str, success: bool) {
+    if json() {
+        emit(&Event::LoginFinished { site, success });
+    }
+}
+
+pub fn submitting(site: &str, url: &str, language: &str) {
+    if json() {
+        emit(&Event::Submitting {
+            site,
+            url,
+            language,
+        });
+    } else {
+        println!("Submitting");
+    }
+}
+
+pub fn submitted(site: &str, id: &str, url: &str) {
+    end_line();
+    if json() {
+        emit(&Event::Submitted { site, id, url });
+    } else {
+        println!("Submission url {}", url);
+    }
+}
+
+/// Shows an intermediate verdict, replacing the previous one on a terminal.
+pub fn verdict(verdict: &str, status: Status) {
+    let mut line = LINE.lock().unwrap();
+    let changed = line.text != verdict;
+    line.text = verdict.to_string();
+    line.status = status;
+    if json() {
+        if changed {
+            emit(&Event::Verdict { verdict, status });
+        }
+    } else if live() {
+        line.shown = true;
+        draw(&line);
+        spawn_ticker();
+    } else if changed {
+        println!("{}", paint(verdict, color(status)));
+    }
+}
+
+/// What a local run is doing, like compiling or running a sample. Only drawn on a terminal, it
+/// is neither an event nor a verdict of a submission.
+pub fn progress(text: &str) {
+    if !live() {
+        return;
+    }
+    let mut line = LINE.lock().unwrap();
+    line.text = text.to_string();
+    line.status = Status::Waiting;
+    line.shown = true;
+    draw(&line);
+    spawn_ticker();
+}
+
+pub fn finish(verdict: &str, status: Status, details: Details) {
+    end_line();
+    if json() {
+        emit(&Event::Final {
+            verdict,
+            status,
+            details: &details,
+        });
+        return;
+    }
+    print_final(verdict, status, details);
+}
+
+/// Outcome of a local run, shaped like `finish` but not a verdict of the judge.
+pub fn result(verdict: &str, status: Status, details: Details) {
+    end_line();
+    if json() {
+        emit(&Event::LocalResult {
+            verdict,
+            status,
+            details: &details,
+        });
+        return;
+    }
+    print_final(verdict, status, details);
+}
+
+fn print_final(verdict: &str, status: Status, details: Details) {
+    print!("{}", paint(verdict, color(status)));
+    let details: Vec<_> = [details.time, details.memory, details.score]
+        .into_iter()
+        .flatten()
+        .filter(|detail| !detail.is_empty())
+        .collect();
+    if details.is_empty() {
+        println!();
+    } else {
+        println!(" ({})", details.join(", "));
+    }
+}
+
+/// Result of a single test or subtask, shown after the final verdict.
+pub fn test(group: &str, test: &str, verdict: &str, status: Status) {
+    if json() {
+        emit(&Event::Test {
+            group,
+            test,
+            verdict,
+            status,
+        });
+    } else {
+        print_test(group, test, verdict, status);
+    }
+}
+
+/// Result of a sample or a generated test run locally.
+pub fn local_test(group: &str, test: &str, verdict: &str, status: Status) {
+    end_line();
+    if json() {
+        emit(&Event::LocalTest {
+            group,
+            test,
+            verdict,
+            status,
+        });
+    } else {
+        print_test(group, test, verdict, status);
+    }
+}
+
+fn print_test(group: &str, test: &str, verdict: &str, status: Status) {
+    println!(
+        "{}",
+        paint(
+            &format!("{:7} {:4} {}", group, test, verdict),
+            color(status)
+        )
+    );
+}
+
+/// Plain text for humans, a header or a table, skipped in json.
+pub fn text(text: &str) {
+    if !json() {
+        println!("{}", text);
+    }
+}
+
+pub fn check(state: CheckState, check: &str, message: &str) {
+    end_line();
+    if json() {
+        emit(&Event::Check {
+            check,
+            state,
+            message,
+        });
+        return;
+    }
+    let (color, label) = match state {
+        CheckState::Ok => (Color::Green, " ok "),
+        CheckState::Warn => (Color::Yellow, "warn"),
+        CheckState::Fail => (Color::Red, "fail"),
+    };
+    println!("[{}] {}: {}", paint(label, color), check, message);
+}
+
+pub fn message(text: &str) {
+    end_line();
+    if json() {
+        emit(&Event::Message { text });
+    } else {
+        println!("{}", text);
+    }
+}
+
+pub fn warning(message: &str) {
+    end_line();
+    if json() {
+        emit(&Event::Warning { message });
+    } else {
+        eprintln!("{}", paint_stderr(message, Color::Yellow));
+    }
+}
+
+pub fn error(message: &str) {
+    end_line();
+    if json() {
+        emit(&Event::Error { message });
+    } else {
+        eprintln!("{}", paint_stderr(message, Color::Red));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::output;
+use crate::server::{self, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::WebDriver;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Index {
+    pages: Vec<Page>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Page {
+    requested: Option<String>,
+    url: String,
+    file: String,
+}
+
+struct Recorder {
+    dir: PathBuf,
+    index: Index,
+    last: Option<(String, String)>,
+}
+
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+static REPLAY_BASE: OnceLock<String> = OnceLock::new();
+
+pub fn start(dir: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    *RECORDER.lock().unwrap() = Some(Recorder {
+        dir: PathBuf::from(dir),
+        index: Index::default(),
+        last: None,
+    });
+    Ok(())
+}
+
+pub fn spawn(driver: WebDriver) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            if snapshot(&driver, None).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+pub async fn snapshot(driver: &WebDriver, requested: Option<&str>) -> WebDriverResult<()> {
+    if RECORDER.lock().unwrap().is_none() {
+        return Ok(());
+    }
+    let url = driver.current_url().await?.to_string();
+    let source = driver.source().await?;
+    let mut recorder = RECORDER.lock().unwrap();
+    let Some(recorder) = recorder.as_mut() else {
+        return Ok(());
+    };
+    if requested.is_none() && recorder.last == Some((url.clone(), source.clone())) {
+        return Ok(());
+    }
+    let file = format!("{:04}.html", recorder.index.pages.len());
+    if let Err(err) = std::fs::write(recorder.dir.join(&file), &source) {
+        output::warning(&format!("Failed to record {}: {}", url, err));
+        return Ok(());
+    }
+    recorder.index.pages.push(Page {
+        requested: requested.map(|requested| requested.to_string()),
+        url: url.clone(),
+        file,
+    });
+    recorder.last = Some((url, source));
+    let index = serde_json::to_string_pretty(&recorder.index).unwrap();
+    let _ = std::fs::write(recorder.dir.join("index.json"), index);
+    Ok(())
+}
+
+pub fn replaying() -> bool {
+    REPLAY_BASE.get().is_some()
+}
+
+/// Rewrites a site url to point at the replay server, if one is running.
+pub fn site_url(url: &str) -> String {
+    let Some(base) = REPLAY_BASE.get() else {
+        return url.to_string();
+    };
+    match strip_scheme(url) {
+        Some(rest) => format!("{}/{}", base, rest),
+        None => url.to_string(),
+    }
+}
+
+fn strip_scheme(url: &str) -> Option<&str> {
+    url.strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+}
+
+fn normalize(url: &str) -> String {
+    strip_scheme(url)
+        .unwrap_or(url)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+struct Replay {
+    dir: PathBuf,
+    pages: Vec<Page>,
+    hosts: BTreeSet<String>,
+    served: HashMap<String, usize>,
+    last: usize,
+}
+
+impl Replay {
+    fn handle(&mut self, request: Request) -> Response {
+        if request.method == "POST" {
+            return self.next_page();
+        }
+        let target = request.target.trim_start_matches('/');
+        let path = request.path.trim_start_matches('/');
+        let host = path.split('/').next().unwrap_or("");
+        let candidates = if self.hosts.contains(host) {
+            vec![target.to_string(), path.to_string()]
+        } else {
+            self.hosts
+                .iter()
+                .flat_map(|host| [format!("{}/{}", host, target), format!("{}/{}", host, path)])
+                .collect()
+        };
+        for candidate in candidates {
+            if let Some(response) = self.serve(candidate.trim_end_matches('/')) {
+                return response;
+            }
+        }
+        Response::not_found()
+    }
+
+    fn serve(&mut self, key: &str) -> Option<Response> {
+        let matching: Vec<usize> = (0..self.pages.len())
+            .filter(|&i| normalize(&self.pages[i].url) == key)
+            .collect();
+        if matching.is_empty() {
+            let page = self.pages.iter().find(|page| {
+                page.requested
+                    .as_ref()
+                    .is_some_and(|requested| normalize(requested) == key)
+            })?;
+            return Some(Response::redirect(&site_url(&page.url)));
+        }
+        let served = self.served.entry(key.to_string()).or_insert(0);
+        let i = matching[(*served).min(matching.len() - 1)];
+        *served += 1;
+        Some(self.page(i))
+    }
+
+    fn next_page(&mut self) -> Response {
+        let current = normalize(&self.pages[self.last].url);
+        match (self.last + 1..self.pages.len()).find(|&i| normalize(&self.pages[i].url) != current)
+        {
+            Some(i) => Response::redirect(&site_url(&self.pages[i].url)),
+            None => Response::not_found(),
+        }
+    }
+
+    fn page(&mut self, i: usize) -> Response {
+        self.last = i;
+        let mut source =
+            std::fs::read_to_string(self.dir.join(&self.pages[i].file)).unwrap_or_default();
+        for host in &self.hosts {
+            source = source.replace(
+                &format!("https://{}", host),
+                &site_url(&format!("https://{}", host)),
+            );
+        }
+        Response::html(source)
+    }
+}
+
+pub async fn replay(dir: &str) -> std::io::Result<()> {
+    let index = std::fs::read_to_string(PathBuf::from(dir).join("index.json"))?;
+    let index: Index = serde_json::from_str(&index)?;
+    if index.pages.is_empty() {
+        return Err(std::io::Error::other("recording is empty"));
+    }
+    let hosts = index
+        .pages
+        .iter()
+        .flat_map(|page| [Some(&page.url), page.requested.as_ref()])
+        .flatten()
+        .filter_map(|url| Some(normalize(url).split('/').next()?.to_string()))
+        .collect();
+    let listener = server::bind(0).await?;
+    let port = listener.local_addr()?.port();
+    let _ = REPLAY_BASE.set(format!("http://{}:{}", server::browser_host(), port));
+    let replay = Mutex::new(Replay {
+        dir: PathBuf::from(dir),
+        pages: index.pages,
+        hosts,
+        served: HashMap::new(),
+        last: 0,
+    });
+    tokio::spawn(server::serve(listener, move |request| {
+        replay.lock().unwrap().handle(request)
+    }));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::output;
+use crate::server;
+use crate::sitedata;
+use crate::Site;
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::WebDriver;
+
+pub async fn run(driver: &WebDriver, only: Option<&str>) -> WebDriverResult<()> {
+    let all_cookies = crate::load_cookies();
+    let mut broken = Vec::new();
+    let mut found = false;
+    for site in Site::all() {
+        if only.is_some_and(|only| only != site.name()) {
+            continue;
+        }
+        found = true;
+        output::message(site.name());
+        let pages = &sitedata::site(site.name()).selftest;
+        if pages.is_empty() {
+            output::message("  no pages to check");
+            continue;
+        }
+        let cookies = all_cookies.get(&site.domain()).cloned().unwrap_or_default();
+        for page in pages {
+            let url = server::browser_url(&page.url);
+            let missing = match check(driver, site.name(), &url, page, &cookies).await {
+                Ok(missing) => missing,
+                Err(err) => {
+                    output::error(&format!(
+                        "  {:7} failed to load {}: {}",
+                        page.page, page.url, err
+                    ));
+                    broken.push(format!("{} {}", site.name(), page.page));
+                    continue;
+                }
+            };
+            if missing.is_empty() {
+                output::message(&format!("  {:7} ok", page.page));
+            } else {
+                output::error(&format!(
+                    "  {:7} missing: {}",
+                    page.page,
+                    missing.join(", ")
+                ));
+                broken.push(format!("{} {}", site.name(), page.page));
+            }
+        }
+    }
+    if !found {
+        output::error(&format!("Unknown site {}", only.unwrap_or("")));
+    } else if broken.is_empty() {
+        output::message("All selectors resolved");
+    } else {
+        output::error(&format!("Broken pages: {}", broken.join(", ")));
+    }
+    Ok(())
+}
+
+async fn check(
+    driver: &WebDriver,
+    name: &str,
+    url: &str,
+    page: &sitedata::SelftestPage,
+    cookies: &[thirtyfour::Cookie],
+) -> WebDriverResult<Vec<String>> {
+    crate::goto(driver, url).await?;
+    driver.delete_all_cookies().await?;
+    if page.page != "login" && !cookies.is_empty() {
+        for cookie in cookies {
+            let _ = driver.add_cookie(cookie.clone()).await;
+        }
+        crate::goto(driver, url).await?;
+    }
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let mut missing = Vec::new();
+    if let Some(follow) = &page.follow {
+        if !sitedata::has_selector(name, follow) {
+            return Ok(vec![format!("{} (not defined)", follow)]);
+        }
+        let Ok(link) = driver.find(sitedata::by(name, follow)).await else {
+            return Ok(vec![follow.clone()]);
+        };
+        let Some(href) = link.prop("href").await? else {
+            return Ok(vec![follow.clone()]);
+        };
+        crate::goto(driver, &href).await?;
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+    for selector in &page.selectors {
+        if !sitedata::site(name).selectors.contains_key(selector) {
+            missing.push(format!("{} (not defined)", selector));
+        } else if driver.find(sitedata::by(name, selector)).await.is_err() {
+            missing.push(selector.clone());
+        }
+    }
+    Ok(missing)
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Request bodies are small forms, anything larger is refused before it is read.
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        let cookies = self.headers.get("cookie")?;
+        for cookie in cookies.split(';') {
+            if let Some((key, value)) = cookie.trim().split_once('=') {
+                if key == name {
+                    return Some(value.to_string());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn form(&self) -> HashMap<String, String> {
+        parse_query(&String::from_utf8_lossy(&self.body))
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn html(body: String) -> Response {
+        Response {
+            status: 200,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/html; charset=utf-8".to_string(),
+            )],
+            body: body.into_bytes(),
+        }
+    }
+
+    pub fn redirect(location: &str) -> Response {
+        Response {
+            status: 303,
+            headers: vec![("Location".to_string(), location.to_string())],
+            body: Vec::new(),
+        }
+    }
+
+    pub fn not_found() -> Response {
+        Response {
+            status: 404,
+            headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+            body: b"Not found".to_vec(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Host name under which the browser reaches servers started by this process.
+/// The default works for the dockerized selenium started by `main`.
+pub fn browser_host() -> String {
+    std::env::var("SUBMITTER_HOST").unwrap_or("host.docker.internal".to_string())
+}
+
+/// Points local urls at the address the browser reaches this host under.
+pub fn browser_url(url: &str) -> String {
+    url.replacen("://localhost:", &format!("://{}:", browser_host()), 1)
+        .replacen("://127.0.0.1:", &format!("://{}:", browser_host()), 1)
+}
+
+/// Listens where the browser can reach, and nowhere else: on loopback when the browser runs on
+/// this host, otherwise on the gateway of the docker bridge that `host.docker.internal` points at.
+/// `SUBMITTER_BIND` overrides the address.
+pub async fn bind(port: u16) -> std::io::Result<TcpListener> {
+    TcpListener::bind((listen_address().await.as_str(), port)).await
+}
+
+async fn listen_address() -> String {
+    if let Ok(address) = std::env::var("SUBMITTER_BIND") {
+        return address;
+    }
+    let host = browser_host();
+    if host == "localhost" {
+        return "127.0.0.1".to_string();
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return host;
+    }
+    let gateway = tokio::process::Command::new("docker")
+        .args([
+            "network",
+            "inspect",
+            "bridge",
+            "--format",
+            "{{range .IPAM.Config}}{{.Gateway}}{{end}}",
+        ])
+        .output()
+        .await;
+    match gateway {
+        Ok(output) if output.status.success() => {
+            let gateway = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if gateway.parse::<IpAddr>().is_ok() {
+                gateway
+            } else {
+                "127.0.0.1".to_string()
+            }
+        }
+        _ => "127.0.0.1".to_string(),
+    }
+}
+
+pub async fn serve<F>(listener: TcpListener, handler: F)
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, handler.as_ref()).await;
+        });
+    }
+}
+
+async fn handle<F>(stream: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(Request) -> Response,
+{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let target = parts.next().unwrap_or("/").to_string();
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        let response = Response {
+            status: 413,
+            headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+            body: b"Payload too large".to_vec(),
+        };
+        return respond(reader.into_inner(), response).await;
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_query(query)),
+        None => (target.clone(), HashMap::new()),
+    };
+    let response = handler(Request {
+        method,
+        target,
+        path,
+        query,
+        headers,
+        body,
+    });
+    respond(reader.into_inner(), response).await
+}
+
+async fn respond(mut stream: TcpStream, response: Response) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head += &format!("{}: {}\r\n", name, value);
+    }
+    head += "\r\n";
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.flush().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        302 => "Found",
+        303 => "See Other",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        _ => "",
+    }
+}
+
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    for pair in query.split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        result.insert(decode(key), decode(value));
+    }
+    result
+}
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => result.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        result.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => result.push(b'%'),
+                }
+            }
+            byte => result.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_reads_form_encoding() {
+        assert_eq!(decode("a+b%3Dc"), "a b=c");
+        assert_eq!(decode("%D0%BF%D1%80%D0%B8"), "при");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz%4"), "%zz%4");
+        assert_eq!(decode("%41"), "A");
+    }
+}
@@ -0,0 +1,186 @@
+use crate::config;
+use crate::output;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use thirtyfour::By;
+
+const DEFAULTS: &str = include_str!("sites.json");
+const OVERRIDE_FILE: &str = "sites.override.json";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Data {
+    pub version: u32,
+    pub sites: HashMap<String, SiteData>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct SiteData {
+    pub urls: HashMap<String, String>,
+    pub selectors: HashMap<String, String>,
+    pub indices: HashMap<String, usize>,
+    pub texts: HashMap<String, String>,
+    pub verdicts: Vec<(String, Status)>,
+    pub selftest: Vec<SelftestPage>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SelftestPage {
+    pub page: String,
+    pub url: String,
+    /// Selector of a link on `url` to the page to check, for pages without a stable url like
+    /// the solution of someone else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow: Option<String>,
+    pub selectors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Waiting,
+    Accepted,
+    Rejected,
+}
+
+static DATA: OnceLock<Data> = OnceLock::new();
+
+/// Named apart from the embedded `src/sites.json` so a copy of the defaults lying around is not
+/// taken for an override.
+pub fn override_path() -> PathBuf {
+    config::config_dir().join(OVERRIDE_FILE)
+}
+
+pub fn data() -> &'static Data {
+    DATA.get_or_init(|| {
+        let mut data: Data = serde_json::from_str(DEFAULTS).unwrap();
+        let path = override_path();
+        let Ok(patch) = std::fs::read_to_string(&path) else {
+            return data;
+        };
+        let patch: Data = match serde_json::from_str(&patch) {
+            Ok(patch) => patch,
+            Err(err) => {
+                output::warning(&format!("Ignoring {}: {}", path.display(), err));
+                return data;
+            }
+        };
+        if patch.version != data.version {
+            output::warning(&format!(
+                "Ignoring {}: written for version {}, expected {}",
+                path.display(),
+                patch.version,
+                data.version
+            ));
+            return data;
+        }
+        for (name, patch) in patch.sites {
+            let site = data.sites.entry(name).or_default();
+            site.urls.extend(patch.urls);
+            site.selectors.extend(patch.selectors);
+            site.indices.extend(patch.indices);
+            site.texts.extend(patch.texts);
+            if !patch.verdicts.is_empty() {
+                site.verdicts = patch.verdicts;
+            }
+            if !patch.selftest.is_empty() {
+                site.selftest = patch.selftest;
+            }
+        }
+        data
+    })
+}
+
+pub fn defaults() -> &'static str {
+    DEFAULTS
+}
+
+pub fn site(site: &str) -> &'static SiteData {
+    data()
+        .sites
+        .get(site)
+        .unwrap_or_else(|| panic!("No data for site {}", site))
+}
+
+fn lookup<'a, T>(map: &'a HashMap<String, T>, site: &str, kind: &str, name: &str) -> &'a T {
+    map.get(name)
+        .unwrap_or_else(|| panic!("No {} {} for site {}", kind, name, site))
+}
+
+pub fn url(name: &str, key: &str) -> String {
+    lookup(&site(name).urls, name, "url", key).clone()
+}
+
+/// Optional urls are only used by sites that define them.
+pub fn has_url(name: &str, key: &str) -> bool {
+    site(name).urls.contains_key(key)
+}
+
+/// Fills `{placeholder}`s of an url template.
+pub fn url_with(name: &str, key: &str, values: &[(&str, &str)]) -> String {
+    let mut url = url(name, key);
+    for (placeholder, value) in values {
+        url = url.replace(&format!("{{{}}}", placeholder), value);
+    }
+    url
+}
+
+pub fn selector(name: &str, key: &str) -> &'static str {
+    lookup(&site(name).selectors, name, "selector", key).as_str()
+}
+
+/// Optional selectors are only used by sites that define them.
+pub fn has_selector(name: &str, key: &str) -> bool {
+    site(name).selectors.contains_key(key)
+}
+
+/// Selectors are css, except for `link:` (partial link text) and `xpath:` prefixes.
+pub fn by(name: &str, key: &str) -> By {
+    to_by(selector(name, key))
+}
+
+/// Fills `{placeholder}`s of a selector the same way as `url_with`.
+pub fn by_with(name: &str, key: &str, values: &[(&str, &str)]) -> By {
+    let mut selector = selector(name, key).to_string();
+    for (placeholder, value) in values {
+        selector = selector.replace(&format!("{{{}}}", placeholder), value);
+    }
+    to_by(&selector)
+}
+
+fn to_by(selector: &str) -> By {
+    if let Some(text) = selector.strip_prefix("link:") {
+        By::PartialLinkText(text.to_string())
+    } else if let Some(xpath) = selector.strip_prefix("xpath:") {
+        By::XPath(xpath.to_string())
+    } else {
+        By::Css(selector.to_string())
+    }
+}
+
+pub fn index(name: &str, key: &str) -> usize {
+    *lookup(&site(name).indices, name, "index", key)
+}
+
+pub fn has_index(name: &str, key: &str) -> bool {
+    site(name).indices.contains_key(key)
+}
+
+pub fn has_text(name: &str, key: &str) -> bool {
+    site(name).texts.contains_key(key)
+}
+
+pub fn text(name: &str, key: &str) -> &'static str {
+    lookup(&site(name).texts, name, "text", key).as_str()
+}
+
+/// Status of the first verdict mapping whose key is contained in `value`.
+pub fn verdict(name: &str, value: &str) -> Option<Status> {
+    site(name)
+        .verdicts
+        .iter()
+        .find(|(key, _)| value.contains(key.as_str()))
+        .map(|(_, status)| *status)
+}
@@ -0,0 +1,256 @@
+use crate::history::{self, Filter, Record};
+use crate::output;
+use crate::sitedata::Status;
+use chrono::DateTime;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize, Default)]
+struct SiteStats {
+    submissions: usize,
+    accepted: usize,
+    solved: usize,
+    acceptance_rate: f64,
+    average_attempts: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Solve {
+    site: String,
+    problem: String,
+    attempts: usize,
+    /// Seconds from the first submission to the first accepted one.
+    time_to_ac: i64,
+}
+
+#[derive(Serialize, Default)]
+struct Stats {
+    sites: BTreeMap<String, SiteStats>,
+    failures: BTreeMap<String, Vec<(String, usize)>>,
+    solves: Vec<Solve>,
+}
+
+/// `Wrong answer on test 5` and `Wrong answer on test 7` are the same failure.
+fn failure(verdict: &str) -> String {
+    let regex = Regex::new(r"(?i)\s*(on test\s*\d+|\d+\s*/\s*\d+|\(.*\))").unwrap();
+    regex.replace_all(verdict, "").trim().to_string()
+}
+
+/// `c++`, `C++20` and `89` all resolve to the label the site showed. Sites that do not show the
+/// language, and records from before it was saved, fall back to the language as requested.
+fn language(record: &Record) -> String {
+    record
+        .language_label
+        .clone()
+        .unwrap_or_else(|| record.language.clone())
+}
+
+fn seconds(record: &Record) -> Option<i64> {
+    DateTime::parse_from_rfc3339(&record.submitted_at)
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+fn collect(records: &[Record]) -> Stats {
+    let mut stats = Stats::default();
+    let mut problems: BTreeMap<(String, String), Vec<&Record>> = BTreeMap::new();
+    let mut failures: BTreeMap<String, HashMap<String, usize>> = BTreeMap::new();
+    for record in records {
+        let site = stats.sites.entry(record.site.clone()).or_default();
+        site.submissions += 1;
+        if record.status == Status::Accepted {
+            site.accepted += 1;
+        } else {
+            *failures
+                .entry(language(record))
+                .or_default()
+                .entry(failure(&record.verdict))
+                .or_default() += 1;
+        }
+        problems
+            .entry((record.site.clone(), record.problem.clone()))
+            .or_default()
+            .push(record);
+    }
+    let mut attempts: HashMap<String, Vec<usize>> = HashMap::new();
+    for ((site, problem), mut submissions) in problems {
+        submissions.sort_by_key(|record| seconds(record));
+        let Some(first_ac) = submissions
+            .iter()
+            .position(|record| record.status == Status::Accepted)
+        else {
+            continue;
+        };
+        attempts.entry(site.clone()).or_default().push(first_ac + 1);
+        let start = seconds(submissions[0]).unwrap_or_default();
+        let end = seconds(submissions[first_ac]).unwrap_or_default();
+        stats.solves.push(Solve {
+            site,
+            problem,
+            attempts: first_ac + 1,
+            time_to_ac: end - start,
+        });
+    }
+    for (name, site) in &mut stats.sites {
+        site.acceptance_rate = site.accepted as f64 / site.submissions as f64;
+        if let Some(attempts) = attempts.get(name) {
+            site.solved = attempts.len();
+            site.average_attempts =
+                Some(attempts.iter().sum::<usize>() as f64 / attempts.len() as f64);
+        }
+    }
+    for (language, verdicts) in failures {
+        let mut verdicts: Vec<_> = verdicts.into_iter().collect();
+        verdicts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        verdicts.truncate(3);
+        stats.failures.insert(language, verdicts);
+    }
+    stats
+}
+
+fn duration(secs: i64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else if secs < 86400 {
+        format!("{}h {}m", secs / 3600, secs / 60 % 60)
+    } else {
+        format!("{}d {}h", secs / 86400, secs / 3600 % 24)
+    }
+}
+
+pub fn run(filter: &Filter) {
+    let records: Vec<_> = history::load()
+        .into_iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+    let stats = collect(&records);
+    if output::json() {
+        println!("{}", serde_json::to_string(&stats).unwrap());
+        return;
+    }
+    if records.is_empty() {
+        println!("No submissions");
+        return;
+    }
+    println!(
+        "{:12} {:>11} {:>8} {:>6} {:>10} {:>12}",
+        "Site", "Submissions", "Accepted", "Solved", "AC rate", "Attempts/AC"
+    );
+    for (name, site) in &stats.sites {
+        println!(
+            "{:12} {:>11} {:>8} {:>6} {:>9.1}% {:>12}",
+            name,
+            site.submissions,
+            site.accepted,
+            site.solved,
+            site.acceptance_rate * 100.0,
+            site.average_attempts
+                .map(|attempts| format!("{:.2}", attempts))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    if !stats.failures.is_empty() {
+        println!();
+        println!("Most common failures");
+        for (language, verdicts) in &stats.failures {
+            let verdicts: Vec<_> = verdicts
+                .iter()
+                .map(|(verdict, count)| format!("{} ({})", verdict, count))
+                .collect();
+            println!("  {:30} {}", language, verdicts.join(", "));
+        }
+    }
+    if !stats.solves.is_empty() {
+        println!();
+        println!("Time to AC");
+        for solve in &stats.solves {
+            println!(
+                "  {:12} {:50} {:>8} {} attempt(s)",
+                solve.site,
+                solve.problem,
+                duration(solve.time_to_ac),
+                solve.attempts
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::Details;
+
+    fn record(problem: &str, at: &str, language: &str, verdict: &str, status: Status) -> Record {
+        Record {
+            submitted_at: format!("2024-05-01T10:{}:00+00:00", at),
+            site: "codeforces".to_string(),
+            problem: problem.to_string(),
+            contest: None,
+            language: language.to_string(),
+            language_label: None,
+            source_hash: String::new(),
+            id: None,
+            url: None,
+            verdict: verdict.to_string(),
+            status,
+            details: Details::default(),
+        }
+    }
+
+    #[test]
+    fn failure_drops_the_test_number() {
+        assert_eq!(failure("Wrong answer on test 5"), "Wrong answer");
+        assert_eq!(failure("Partial 3/10"), "Partial");
+        assert_eq!(failure("Runtime error (SIGSEGV)"), "Runtime error");
+    }
+
+    #[test]
+    fn collect_counts_attempts_until_the_first_accepted() {
+        let records = [
+            record("A", "05", "c++", "Wrong answer on test 2", Status::Rejected),
+            record("A", "00", "c++", "Wrong answer on test 1", Status::Rejected),
+            record("A", "10", "c++", "Accepted", Status::Accepted),
+            record("A", "20", "c++", "Accepted", Status::Accepted),
+            record(
+                "B",
+                "30",
+                "python",
+                "Time limit exceeded on test 3",
+                Status::Rejected,
+            ),
+        ];
+        let stats = collect(&records);
+        let site = &stats.sites["codeforces"];
+        assert_eq!((site.submissions, site.accepted, site.solved), (5, 2, 1));
+        assert_eq!(site.acceptance_rate, 0.4);
+        assert_eq!(site.average_attempts, Some(3.0));
+        assert_eq!(stats.solves.len(), 1);
+        assert_eq!(
+            (stats.solves[0].attempts, stats.solves[0].time_to_ac),
+            (3, 600)
+        );
+        assert_eq!(stats.failures["c++"], vec![("Wrong answer".to_string(), 2)]);
+        assert_eq!(
+            stats.failures["python"],
+            vec![("Time limit exceeded".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn collect_groups_failures_by_the_label_shown() {
+        let mut records = [
+            record("A", "00", "c++", "Wrong answer", Status::Rejected),
+            record("A", "05", "C++20", "Wrong answer", Status::Rejected),
+            record("A", "10", "c++", "Wrong answer", Status::Rejected),
+        ];
+        for record in &mut records[..2] {
+            record.language_label = Some("GNU G++20 13.2".to_string());
+        }
+        let stats = collect(&records);
+        assert_eq!(stats.failures["GNU G++20 13.2"][0].1, 2);
+        assert_eq!(stats.failures["c++"][0].1, 1);
+    }
+}
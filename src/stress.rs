@@ -0,0 +1,200 @@
+use crate::checker::Judge;
+use crate::limits::Limits;
+use crate::output::{self, Details};
+use crate::sitedata::Status;
+use crate::tester::{self, Build, Execution, Outcome, Report};
+use std::path::Path;
+use std::time::Duration;
+
+const INPUT: &str = ".submitter/stress.in";
+const ANSWER: &str = ".submitter/stress.out";
+/// Given to the generator and the brute force, which may be slow on purpose.
+const HELPER_LIMITS: Limits = Limits {
+    time: Duration::from_secs(10),
+    memory_mb: None,
+};
+/// Seeds tried at every smaller size when shrinking.
+const SHRINK_ATTEMPTS: u64 = 100;
+/// Failing inputs up to this many lines are shown.
+const SHOWN_LINES: usize = 20;
+
+pub struct Programs {
+    pub solution: Build,
+    pub brute: Build,
+    /// Started as `generator <seed> [size]`, printing a test.
+    pub generator: Build,
+    pub judge: Judge,
+}
+
+pub struct Options {
+    pub seed: u64,
+    pub iterations: u64,
+    /// Passed to the generator and halved while the solution still fails.
+    pub size: Option<u64>,
+    pub limits: Limits,
+}
+
+struct Failure {
+    seed: u64,
+    size: Option<u64>,
+    input: Vec<u8>,
+    answer: Vec<u8>,
+    report: Report,
+}
+
+/// Generates a test, answers it with the brute force and judges the solution on it. `Err` when
+/// the generator or the brute force fails, since nothing can be checked then.
+async fn attempt(
+    programs: &Programs,
+    seed: u64,
+    size: Option<u64>,
+    limits: Limits,
+) -> Result<Option<Failure>, String> {
+    let mut args = vec![seed.to_string()];
+    if let Some(size) = size {
+        args.push(size.to_string());
+    }
+    let (generated, _) = tester::execute(&programs.generator, &args, None, HELPER_LIMITS).await;
+    let input = match generated {
+        Execution::Finished(input) => input,
+        Execution::Crashed(message) => {
+            return Err(format!("Generator failed on seed {}: {}", seed, message))
+        }
+        Execution::TimedOut => return Err(format!("Generator timed out on seed {}", seed)),
+        Execution::MemoryLimit => {
+            return Err(format!("Generator ran out of memory on seed {}", seed))
+        }
+    };
+    tester::create_parent(Path::new(INPUT))
+        .and_then(|_| std::fs::write(INPUT, &input))
+        .map_err(|err| format!("Failed to write {}: {}", INPUT, err))?;
+    let (answered, _) =
+        tester::execute(&programs.brute, &[], Some(Path::new(INPUT)), HELPER_LIMITS).await;
+    let answer = match answered {
+        Execution::Finished(answer) => answer,
+        Execution::Crashed(message) => {
+            return Err(format!("Brute force failed on seed {}: {}", seed, message))
+        }
+        Execution::TimedOut => return Err(format!("Brute force timed out on seed {}", seed)),
+        Execution::MemoryLimit => {
+            return Err(format!("Brute force ran out of memory on seed {}", seed))
+        }
+    };
+    std::fs::write(ANSWER, &answer)
+        .map_err(|err| format!("Failed to write {}: {}", ANSWER, err))?;
+    let report = tester::check(
+        &programs.solution,
+        &programs.judge,
+        Path::new(INPUT),
+        Path::new(ANSWER),
+        limits,
+    )
+    .await;
+    if matches!(report.outcome, Outcome::Accepted) {
+        return Ok(None);
+    }
+    Ok(Some(Failure {
+        seed,
+        size,
+        input,
+        answer,
+        report,
+    }))
+}
+
+/// Looks for a smaller failing test by halving the size, trying a few seeds at each.
+async fn shrink(programs: &Programs, mut failure: Failure, limits: Limits) -> Failure {
+    let Some(mut size) = failure.size else {
+        return failure;
+    };
+    while size > 1 {
+        size /= 2;
+        output::progress(&format!("Shrinking to size {}", size));
+        let mut smaller = None;
+        for seed in failure.seed + 1..=failure.seed + SHRINK_ATTEMPTS {
+            match attempt(programs, seed, Some(size), limits).await {
+                // The generator may not support sizes this small.
+                Err(_) => break,
+                Ok(Some(found)) => {
+                    smaller = Some(found);
+                    break;
+                }
+                Ok(None) => {}
+            }
+        }
+        match smaller {
+            Some(smaller) => failure = smaller,
+            None => break,
+        }
+    }
+    failure
+}
+
+/// Runs the solution against the brute force on generated tests until they disagree, saving the
+/// first failing test as the next sample in `dir`. True if no test failed.
+pub async fn run(programs: &Programs, options: &Options, dir: &Path) -> bool {
+    let mut found = None;
+    for seed in options.seed..options.seed + options.iterations {
+        output::progress(&format!(
+            "Test {} of {}, seed {}",
+            seed - options.seed + 1,
+            options.iterations,
+            seed
+        ));
+        match attempt(programs, seed, options.size, options.limits).await {
+            Err(message) => {
+                output::error(&message);
+                return false;
+            }
+            Ok(Some(failure)) => {
+                found = Some(failure);
+                break;
+            }
+            Ok(None) => {}
+        }
+    }
+    let Some(failure) = found else {
+        output::result(
+            &format!("No difference on {} tests", options.iterations),
+            Status::Accepted,
+            Details::default(),
+        );
+        return true;
+    };
+    let failure = shrink(programs, failure, options.limits).await;
+    tester::show(
+        "seed",
+        &failure.seed.to_string(),
+        &failure.report,
+        options.limits,
+    );
+    let input = String::from_utf8_lossy(&failure.input);
+    if input.lines().count() <= SHOWN_LINES {
+        output::message(&format!("Input:\n{}", input.trim_end()));
+    }
+    let number = tester::samples(dir, false)
+        .last()
+        .map_or(1, |last| last + 1);
+    let saved = std::fs::create_dir_all(dir).and_then(|_| {
+        std::fs::write(dir.join(format!("{}.in", number)), &failure.input)?;
+        std::fs::write(dir.join(format!("{}.out", number)), &failure.answer)
+    });
+    match saved {
+        Ok(()) => output::message(&format!(
+            "Saved the test as sample {} in {}",
+            number,
+            dir.display()
+        )),
+        Err(err) => output::error(&format!("Failed to save to {}: {}", dir.display(), err)),
+    }
+    let size = failure
+        .size
+        .map(|size| format!(", size {}", size))
+        .unwrap_or_default();
+    output::result(
+        &format!("Failed on seed {}{}", failure.seed, size),
+        Status::Rejected,
+        Details::default(),
+    );
+    false
+}
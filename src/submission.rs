@@ -0,0 +1,54 @@
+use crate::history;
+use crate::inflight;
+use crate::output::{self, Details};
+use crate::sitedata::Status;
+
+/// Starts keeping the submission of this process in the history and the in-flight list.
+pub fn start(site: &str, url: &str, language: &str, source: &str) {
+    history::start(site, url, language, source);
+    inflight::start(site, url, language);
+    output::submitting(site, url, language);
+}
+
+/// The site accepted the submission, its id is the last part of `url`.
+pub fn submitted(site: &str, url: &str) {
+    let id = url.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    inflight::update(|entry| entry.url = Some(url.to_string()));
+    history::submitted(id, url);
+    output::submitted(site, id, url);
+}
+
+/// An intermediate verdict, saved for `watch` only when it changed.
+pub fn verdict(verdict: &str, status: Status) {
+    inflight::update_if(
+        |entry| entry.verdict != verdict || entry.status != status,
+        |entry| {
+            entry.verdict = verdict.to_string();
+            entry.status = status;
+        },
+    );
+    output::verdict(verdict, status);
+}
+
+pub fn finish(verdict: &str, status: Status, details: Details) {
+    inflight::update(|entry| {
+        entry.verdict = verdict.to_string();
+        entry.status = status;
+        entry.finished = Some(inflight::now());
+    });
+    history::finish(verdict, status, &details);
+    output::finish(verdict, status, details);
+}
+
+/// An error of the site driver, which ends the submission if one is pending.
+pub fn error(message: &str) {
+    history::error(message);
+    inflight::update(|entry| {
+        if entry.finished.is_none() {
+            entry.verdict = message.to_string();
+            entry.status = Status::Rejected;
+            entry.finished = Some(inflight::now());
+        }
+    });
+    output::error(message);
+}
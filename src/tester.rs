@@ -0,0 +1,598 @@
+use crate::bundle;
+use crate::checker::{Judge, Verdict};
+use crate::config;
+use crate::diff;
+use crate::history;
+use crate::interact;
+use crate::limits::{self, Finished, Limits, Usage};
+use crate::output::{self, Details};
+use crate::sitedata::Status;
+use crate::transform::Transformed;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
+
+const BUILD_DIR: &str = ".submitter/build";
+/// Marks a build directory whose compilation finished.
+const BUILT: &str = ".built";
+/// Time limit when neither `--time-limit` nor `problem.json` give one.
+pub const DEFAULT_TIME_LIMIT_MS: u64 = 2000;
+
+/// How to build and start a solution. Commands run through `sh -c` with `{source}`, `{binary}`,
+/// `{dir}` and `{name}` replaced.
+#[derive(Deserialize, Clone)]
+pub struct Language {
+    #[serde(default)]
+    pub compile: Option<String>,
+    pub run: String,
+    /// Whether the memory limit applies to the address space, off for runtimes reserving much
+    /// more than they use. Peak memory is checked either way.
+    #[serde(default = "limit_memory")]
+    pub limit_memory: bool,
+}
+
+/// A compiled solution, ready to run.
+pub struct Build {
+    /// Shell command starting the program.
+    pub run: String,
+    pub limit_memory: bool,
+}
+
+/// Decides whether a run on a sample passed.
+pub enum Referee<'a> {
+    /// Compares the output with `<n>.out`.
+    Checker(&'a Judge),
+    /// Talks to the solution, reading `<n>.in` itself.
+    Interactor(&'a Build),
+}
+
+pub enum Outcome {
+    Accepted,
+    WrongAnswer,
+    RuntimeError,
+    TimeLimit,
+    MemoryLimit,
+    /// The checker or interactor failed, not the solution.
+    Failed,
+}
+
+/// Result of one sample, with what to show under its verdict line.
+pub struct Report {
+    pub outcome: Outcome,
+    pub elapsed: Duration,
+    /// Peak memory, when it was measured.
+    pub memory_kb: Option<u64>,
+    pub messages: Vec<String>,
+    /// Expected and actual output, shown as a diff.
+    pub diff: Option<(String, String)>,
+}
+
+impl Report {
+    pub fn new(outcome: Outcome, elapsed: Duration) -> Report {
+        Report {
+            outcome,
+            elapsed,
+            memory_kb: None,
+            messages: Vec::new(),
+            diff: None,
+        }
+    }
+
+    pub fn message(mut self, message: String) -> Report {
+        self.messages.push(message);
+        self
+    }
+}
+
+fn limit_memory() -> bool {
+    true
+}
+
+fn language(extension: &str) -> Option<Language> {
+    if let Some(language) = config::get().languages.get(extension) {
+        return Some(language.clone());
+    }
+    let (compile, run) = match extension {
+        "cpp" | "cc" | "cxx" => (Some("g++ -std=c++17 -O2 -o {binary} {source}"), "{binary}"),
+        "c" => (Some("gcc -O2 -o {binary} {source} -lm"), "{binary}"),
+        "rs" => (Some("rustc -O -o {binary} {source}"), "{binary}"),
+        "go" => (Some("go build -o {binary} {source}"), "{binary}"),
+        "java" => (Some("javac -d {dir} {source}"), "java -cp {dir} {name}"),
+        "kt" => (
+            Some("kotlinc {source} -include-runtime -d {dir}/main.jar"),
+            "java -jar {dir}/main.jar",
+        ),
+        "py" => (None, "python3 {source}"),
+        _ => return None,
+    };
+    Some(Language {
+        compile: compile.map(str::to_string),
+        run: run.to_string(),
+        limit_memory: !matches!(extension, "go" | "java" | "kt"),
+    })
+}
+
+fn extension(file: &Path) -> String {
+    file.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Whether `file` is a source in a language with known commands.
+/// Creates the directory `path` goes in, like `.submitter` of a run that did not compile there.
+pub fn create_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) => std::fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+pub fn known(file: &Path) -> bool {
+    language(&extension(file)).is_some()
+}
+
+fn fill(command: &str, dir: &Path, source: &Path, name: &str) -> String {
+    command
+        .replace("{source}", &source.display().to_string())
+        .replace("{binary}", &dir.join("main").display().to_string())
+        .replace("{dir}", &dir.display().to_string())
+        .replace("{name}", name)
+}
+
+/// Compiles the final source of `file` once per file name, source and compile command, reporting
+/// compilation errors against the original files. The name is part of the key since the build
+/// keeps the source under it and `{name}` can pick a class from it.
+pub async fn compile(file: &Path, transformed: &Transformed) -> Option<Build> {
+    let extension = extension(file);
+    let Some(language) = language(&extension) else {
+        output::error(&format!(
+            "No commands for .{} files, add them to languages in submitter.json",
+            extension
+        ));
+        return None;
+    };
+    let name = file
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let compile = language.compile.clone().unwrap_or_default();
+    let dir = Path::new(BUILD_DIR).join(history::hash(&format!(
+        "{}\0{}\0{}",
+        file.file_name().unwrap_or_default().to_string_lossy(),
+        compile,
+        transformed.source
+    )));
+    let source = dir.join(file.file_name().unwrap_or_default());
+    let build = Build {
+        run: fill(&language.run, &dir, &source, &name),
+        limit_memory: language.limit_memory,
+    };
+    if dir.join(BUILT).exists() {
+        return Some(build);
+    }
+    let written =
+        std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&source, &transformed.source));
+    if let Err(err) = written {
+        output::error(&format!("Failed to write {}: {}", source.display(), err));
+        return None;
+    }
+    if let Some(compile) = &language.compile {
+        output::progress("Compiling");
+        let result = Command::new("sh")
+            .arg("-c")
+            .arg(fill(compile, &dir, &source, &name))
+            .stdin(Stdio::null())
+            .output()
+            .await;
+        let compiled = match result {
+            Ok(result) if result.status.success() => true,
+            Ok(result) => {
+                let mut errors = String::from_utf8_lossy(&result.stderr).to_string();
+                errors.push_str(&String::from_utf8_lossy(&result.stdout));
+                let built = source.display().to_string();
+                if let Some(bundle) = &transformed.bundle {
+                    errors = bundle.translate(&built, &errors);
+                }
+                errors = errors.replace(&built, &file.display().to_string());
+                output::result("Compilation error", Status::Rejected, Details::default());
+                output::message(errors.trim_end());
+                false
+            }
+            Err(err) => {
+                output::error(&format!("Failed to run the compiler: {}", err));
+                false
+            }
+        };
+        if !compiled {
+            return None;
+        }
+    }
+    let _ = std::fs::write(dir.join(BUILT), "");
+    Some(build)
+}
+
+/// Builds a checker or interactor given as a source file, with its local headers like
+/// `testlib.h` inlined, or runs any other file as it is.
+pub async fn helper(path: &Path) -> Option<Build> {
+    if !known(path) {
+        return Some(Build {
+            run: shell_quote(&path.display().to_string()),
+            limit_memory: true,
+        });
+    }
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            output::error(&format!("Failed to read {}: {}", path.display(), err));
+            return None;
+        }
+    };
+    let mut transformed = Transformed {
+        source,
+        warnings: Vec::new(),
+        bundle: None,
+    };
+    if bundle::applies(path) {
+        match bundle::bundle(path, &transformed.source) {
+            Ok(bundle) => {
+                transformed.source = bundle.source.clone();
+                transformed.bundle = Some(bundle);
+            }
+            Err(err) => {
+                output::error(&err);
+                return None;
+            }
+        }
+    }
+    compile(path, &transformed).await
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Sample numbers with `<n>.in` in `dir`, and `<n>.out` if `answers` are needed, in order.
+pub fn samples(dir: &Path, answers: bool) -> Vec<usize> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut samples: Vec<usize> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "in" {
+                return None;
+            }
+            let number = path.file_stem()?.to_str()?.parse().ok()?;
+            (!answers || dir.join(format!("{}.out", number)).exists()).then_some(number)
+        })
+        .collect();
+    samples.sort();
+    samples
+}
+
+/// Outputs match when their lines do, ignoring trailing whitespace and trailing empty lines.
+pub fn same_output(expected: &str, actual: &str) -> bool {
+    let lines = |text: &str| {
+        let mut lines: Vec<String> = text
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines
+    };
+    lines(expected) == lines(actual)
+}
+
+/// Last lines the program wrote to stderr, after how it exited.
+pub fn crash_message(status: ExitStatus, stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<_> = stderr.lines().collect();
+    let mut message = limits::describe(status);
+    for line in &lines[lines.len().saturating_sub(5)..] {
+        message = format!("{}\n{}", message, line);
+    }
+    message
+}
+
+/// How a single run of a program ended.
+pub enum Execution {
+    /// Exited successfully with this output.
+    Finished(Vec<u8>),
+    Crashed(String),
+    TimedOut,
+    MemoryLimit,
+}
+
+/// Whether a crash looks like a failed allocation under the address space limit.
+fn out_of_memory(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr);
+    [
+        "bad_alloc",
+        "MemoryError",
+        "OutOfMemoryError",
+        "memory allocation of",
+        "out of memory",
+        "Cannot allocate memory",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
+/// Runs the program with `args` appended, reading `input` if given, within `limits`.
+pub async fn execute(
+    build: &Build,
+    args: &[String],
+    input: Option<&Path>,
+    limits: Limits,
+) -> (Execution, Usage) {
+    let stdin = match input.map(std::fs::File::open) {
+        None => Stdio::null(),
+        Some(Ok(stdin)) => stdin.into(),
+        Some(Err(err)) => return (Execution::Crashed(err.to_string()), Usage::default()),
+    };
+    let mut command = std::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("exec {} \"$@\"", build.run))
+        .arg(&build.run)
+        .args(args)
+        .stdin(stdin);
+    let finished = match limits::run(&mut command, limits, build.limit_memory).await {
+        Ok(finished) => finished,
+        Err(err) => return (Execution::Crashed(err.to_string()), Usage::default()),
+    };
+    let usage = finished.usage;
+    (ending(build, limits, finished), usage)
+}
+
+/// How a finished run of `build` ended, judged by what it used within `limits`.
+pub fn ending(build: &Build, limits: Limits, finished: Finished) -> Execution {
+    let usage = finished.usage;
+    let memory_exceeded = limits.memory_mb.is_some_and(|mb| usage.peak_kb > mb * 1024);
+    if finished.timed_out || usage.cpu > limits.time || limits::cpu_exceeded(finished.status) {
+        Execution::TimedOut
+    } else if memory_exceeded
+        || (!finished.status.success()
+            && build.limit_memory
+            && limits.memory_mb.is_some()
+            && out_of_memory(&finished.stderr))
+    {
+        Execution::MemoryLimit
+    } else if !finished.status.success() {
+        Execution::Crashed(crash_message(finished.status, &finished.stderr))
+    } else {
+        Execution::Finished(finished.stdout)
+    }
+}
+
+/// Runs the solution on `input` and judges its output against `answer`.
+pub async fn check(
+    build: &Build,
+    judge: &Judge,
+    input: &Path,
+    answer: &Path,
+    limits: Limits,
+) -> Report {
+    let (execution, usage) = execute(build, &[], Some(input), limits).await;
+    let mut report = Report::new(Outcome::Accepted, usage.cpu);
+    // Unknown where the resource usage of processes cannot be read.
+    report.memory_kb = (usage.peak_kb > 0).then_some(usage.peak_kb);
+    let actual = match execution {
+        Execution::Finished(actual) => String::from_utf8_lossy(&actual).to_string(),
+        Execution::Crashed(message) => {
+            report.outcome = Outcome::RuntimeError;
+            return report.message(message);
+        }
+        Execution::TimedOut => {
+            report.outcome = Outcome::TimeLimit;
+            return report;
+        }
+        Execution::MemoryLimit => {
+            report.outcome = Outcome::MemoryLimit;
+            return report;
+        }
+    };
+    match judge.check(input, &actual, answer).await {
+        Verdict::Accepted => report,
+        Verdict::WrongAnswer(reason) => {
+            report.outcome = Outcome::WrongAnswer;
+            if let Some(reason) = reason {
+                report = report.message(reason);
+            }
+            let expected = std::fs::read_to_string(answer).unwrap_or_default();
+            report.diff = Some((expected, actual));
+            report
+        }
+        Verdict::Failed(message) => {
+            report.outcome = Outcome::Failed;
+            report.message(message)
+        }
+    }
+}
+
+fn megabytes(kb: u64) -> String {
+    format!("{:.1} MB", kb as f64 / 1024.0)
+}
+
+/// Prints the verdict line of a test and what the report has to say about it.
+pub fn show(group: &str, test: &str, report: &Report, limits: Limits) {
+    let (code, status) = match report.outcome {
+        Outcome::Accepted => ("AC", Status::Accepted),
+        Outcome::WrongAnswer => ("WA", Status::Rejected),
+        Outcome::RuntimeError => ("RE", Status::Rejected),
+        Outcome::TimeLimit => ("TLE", Status::Rejected),
+        Outcome::MemoryLimit => ("MLE", Status::Rejected),
+        Outcome::Failed => ("FAIL", Status::Rejected),
+    };
+    let mut verdict = match report.outcome {
+        Outcome::TimeLimit => format!("{:4} >{} ms", code, limits.time.as_millis()),
+        _ => format!("{:4} {} ms", code, report.elapsed.as_millis()),
+    };
+    match (&report.outcome, report.memory_kb, limits.memory_mb) {
+        (Outcome::MemoryLimit, _, Some(limit)) => verdict = format!("{}, >{} MB", verdict, limit),
+        (_, Some(kb), _) => verdict = format!("{}, {}", verdict, megabytes(kb)),
+        _ => {}
+    }
+    output::local_test(group, test, &verdict, status);
+    for message in &report.messages {
+        output::message(message);
+    }
+    if let Some((expected, actual)) = &report.diff {
+        diff::print(expected, actual);
+    }
+}
+
+/// Runs the compiled solution on every sample in `dir`, true if all of them pass.
+pub async fn run(build: &Build, referee: Referee<'_>, dir: &Path, limits: Limits) -> bool {
+    let interactive = matches!(referee, Referee::Interactor(_));
+    let samples = samples(dir, !interactive);
+    if samples.is_empty() {
+        output::error(&format!("No samples in {}, run fetch first", dir.display()));
+        return false;
+    }
+    let mut failed = None;
+    let mut slowest = Duration::ZERO;
+    let mut peak = None;
+    for number in &samples {
+        output::progress(&format!("Running on sample {}", number));
+        let input = dir.join(format!("{}.in", number));
+        let answer = dir.join(format!("{}.out", number));
+        let report = match referee {
+            Referee::Checker(judge) => check(build, judge, &input, &answer, limits).await,
+            Referee::Interactor(interactor) => {
+                interact::run(build, interactor, &input, *number, limits).await
+            }
+        };
+        slowest = slowest.max(report.elapsed);
+        peak = peak.max(report.memory_kb);
+        show("sample", &number.to_string(), &report, limits);
+        if !matches!(report.outcome, Outcome::Accepted) && failed.is_none() {
+            failed = Some(*number);
+        }
+    }
+    let details = Details {
+        time: Some(format!("{} ms", slowest.as_millis())),
+        memory: peak.map(megabytes),
+        ..Default::default()
+    };
+    match failed {
+        None => output::result(
+            &format!("Passed {} sample(s)", samples.len()),
+            Status::Accepted,
+            details,
+        ),
+        Some(number) => output::result(
+            &format!("Failed on sample {}", number),
+            Status::Rejected,
+            details,
+        ),
+    }
+    failed.is_none()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    const LIMITS: Limits = Limits {
+        time: Duration::from_secs(1),
+        memory_mb: Some(256),
+    };
+
+    fn build() -> Build {
+        Build {
+            run: "./a.out".to_string(),
+            limit_memory: true,
+        }
+    }
+
+    /// `status` is a raw wait status, the exit code shifted by 8 or the signal.
+    fn finished(status: i32, stderr: &str, cpu_ms: u64, peak_kb: u64) -> Finished {
+        Finished {
+            status: ExitStatus::from_raw(status),
+            stdout: b"42\n".to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+            usage: Usage {
+                cpu: Duration::from_millis(cpu_ms),
+                peak_kb,
+            },
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn ending_of_a_successful_run_is_its_output() {
+        let ending = ending(&build(), LIMITS, finished(0, "", 100, 1024));
+        assert!(matches!(ending, Execution::Finished(output) if output == b"42\n"));
+    }
+
+    #[test]
+    fn ending_over_the_time_limit() {
+        let cases = [
+            finished(0, "", 1500, 1024),
+            finished(libc::SIGXCPU, "", 900, 1024),
+            Finished {
+                timed_out: true,
+                ..finished(libc::SIGKILL, "", 10, 1024)
+            },
+        ];
+        for case in cases {
+            assert!(matches!(
+                ending(&build(), LIMITS, case),
+                Execution::TimedOut
+            ));
+        }
+    }
+
+    #[test]
+    fn ending_over_the_memory_limit() {
+        let peak = finished(0, "", 100, 300 * 1024);
+        assert!(matches!(
+            ending(&build(), LIMITS, peak),
+            Execution::MemoryLimit
+        ));
+        let allocation = finished(libc::SIGABRT, "std::bad_alloc", 100, 1024);
+        assert!(matches!(
+            ending(&build(), LIMITS, allocation),
+            Execution::MemoryLimit
+        ));
+        // Without the address space limit a failed allocation is a crash like any other.
+        let unlimited = Build {
+            limit_memory: false,
+            ..build()
+        };
+        let allocation = finished(libc::SIGABRT, "std::bad_alloc", 100, 1024);
+        assert!(matches!(
+            ending(&unlimited, LIMITS, allocation),
+            Execution::Crashed(_)
+        ));
+    }
+
+    #[test]
+    fn ending_of_a_crash_names_the_signal() {
+        let crash = finished(libc::SIGSEGV, "line 1\nline 2", 100, 1024);
+        let Execution::Crashed(message) = ending(&build(), LIMITS, crash) else {
+            panic!("not a crash");
+        };
+        assert!(message.starts_with("Killed by SIGSEGV"));
+        assert!(message.ends_with("line 1\nline 2"));
+        let exit = finished(3 << 8, "", 100, 1024);
+        assert!(matches!(
+            ending(&build(), LIMITS, exit),
+            Execution::Crashed(_)
+        ));
+    }
+
+    #[test]
+    fn same_output_ignores_trailing_whitespace() {
+        assert!(same_output("1 2\n3\n", "1 2  \r\n3\n\n\n"));
+        assert!(!same_output("1 2\n3\n", "1  2\n3\n"));
+    }
+}
@@ -1,39 +1,49 @@
-use crate::clear;
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::sitedata::{self, Status};
+use crate::submission;
 use dialoguer::{Input, Password};
 use thirtyfour::error::{WebDriverError, WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://toph.co").await?;
+const SITE: &str = "toph";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://toph.co/login").await?;
-    if driver.current_url().await?.as_str() != "https://toph.co/login" {
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
+    Ok(driver.current_url().await?.as_str() != crate::site_url(&sitedata::url(SITE, "login")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your toph login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your toph password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
-    let inputs = driver.find_all(By::Tag("input")).await?;
+    let inputs = driver.find_all(sitedata::by(SITE, "login_inputs")).await?;
     if inputs.len() != 2 {
-        println!("Failed to find login and password inputs");
+        submission::error("Failed to find login and password inputs");
         return Err(WebDriverError::ParseError(
             "Failed to find login and password inputs".to_string(),
         ));
     }
     inputs[0].send_keys(login).await?;
     inputs[1].send_keys(password).await?;
-    driver.find(By::Tag("button")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "login_button"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
     Ok(driver.get_all_cookies().await?)
 }
@@ -44,38 +54,80 @@ pub async fn submit(
     _language: String,
     source: String,
 ) -> WebDriverResult<()> {
-    println!("Cannot change language on toph, language of last submit would be used");
+    output::message("Cannot change language on toph, language of last submit would be used");
     driver.maximize_window().await?;
-    driver.goto(&url).await?;
-    for button in driver.find_all(By::Tag("button")).await? {
-        let class_name = button.class_name().await?;
-        if class_name.is_some() && class_name.unwrap().contains("btn-codepanel") {
-            button.click().await?;
-            break;
-        }
+    crate::goto(driver, &url).await?;
+    if let Ok(button) = driver.find(sitedata::by(SITE, "open_editor")).await {
+        button.click().await?;
     }
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     let source = escape_html(&source);
     driver
         .execute(
-            "document.getElementsByClassName('cm-content')[0].innerHTML = arguments[0];",
-            vec![serde_json::to_value(source).unwrap()],
+            "document.querySelector(arguments[1]).innerHTML = arguments[0];",
+            vec![
+                serde_json::to_value(source).unwrap(),
+                serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap(),
+            ],
         )
         .await?;
-    let codepanel = driver.find(By::ClassName("codepanel")).await?;
-    let buttons = codepanel.find_all(By::Tag("button")).await?;
-    if buttons.len() < 14 {
-        println!("Failed to find submit button");
+    let codepanel = driver.find(sitedata::by(SITE, "codepanel")).await?;
+    let buttons = codepanel
+        .find_all(sitedata::by(SITE, "codepanel_buttons"))
+        .await?;
+    let Some(button) = buttons.get(sitedata::index(SITE, "submit_button")) else {
+        submission::error("Failed to find submit button");
+        return Ok(());
+    };
+    let form = guard::Form {
+        // The editor keeps the language of the last submit and does not show it.
+        language: None,
+        language_matches: false,
+        source: editor_source(driver).await?,
+    };
+    if !guard::ready(driver, form).await? {
         return Ok(());
     }
-    buttons[13].click().await?;
+    button.click().await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    if driver.current_url().await?.as_str().contains("/p/") {
-        let toast = driver.find(By::ClassName("toast")).await?;
-        println!("Error submitting: {}", toast.text().await?);
+    if driver
+        .current_url()
+        .await?
+        .as_str()
+        .contains(sitedata::text(SITE, "problem_path"))
+    {
+        let toast = driver.find(sitedata::by(SITE, "toast")).await?;
+        submission::error(&format!("Error submitting: {}", toast.text().await?));
         return Ok(());
     }
-    println!("Submission url {}", driver.current_url().await?);
+    submission::submitted(SITE, driver.current_url().await?.as_str());
+    follow(driver).await
+}
+
+/// Contents of the CodeMirror editor, from its state when the page exposes it.
+async fn editor_source(driver: &WebDriver) -> WebDriverResult<String> {
+    let value = driver
+        .execute(
+            "var e = document.querySelector(arguments[0]);\
+            var view = e.cmView && e.cmView.view;\
+            return view ? view.state.doc.toString() : e.innerText;",
+            vec![serde_json::to_value(sitedata::selector(SITE, "editor")).unwrap()],
+        )
+        .await?;
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    if !url.contains("/s/") {
+        submission::error("Bad submission url");
+        return Ok(());
+    }
+    crate::goto(driver, &url).await?;
+    follow(driver).await
+}
+
+/// Polls the submission shown on the open page until its verdict is final.
+async fn follow(driver: &WebDriver) -> WebDriverResult<()> {
     let mut last_verdict = "".to_string();
     loop {
         match single_iteration(driver, &mut last_verdict).await {
@@ -100,7 +152,7 @@ pub async fn submit(
 }
 
 async fn single_iteration(driver: &WebDriver, last_verdict: &mut String) -> WebDriverResult<bool> {
-    let table = driver.find(By::ClassName("table")).await?;
+    let table = driver.find(sitedata::by(SITE, "status_table")).await?;
     let rows = table.find_all(By::Tag("tr")).await?;
     if rows.len() < 2 {
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
@@ -108,11 +160,11 @@ async fn single_iteration(driver: &WebDriver, last_verdict: &mut String) -> WebD
     }
     let row = &rows[1];
     let columns = row.find_all(By::Tag("td")).await?;
-    if columns.len() < 6 {
+    let Some(column) = columns.get(sitedata::index(SITE, "verdict_column")) else {
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         return Ok(false);
-    }
-    let verdict = columns[5].find(By::Tag("span")).await?;
+    };
+    let verdict = column.find(By::Tag("span")).await?;
     let mut verdict_text = verdict
         .inner_html()
         .await?
@@ -127,39 +179,20 @@ async fn single_iteration(driver: &WebDriver, last_verdict: &mut String) -> WebD
         verdict_text = verdict_text.replace("  ", " ");
     }
     let class_name = verdict.class_name().await?.unwrap_or("".to_string());
-    // println!("{:?}", class_name);
-    let (is_accepted, is_done) = if class_name.contains("font-red") {
-        (false, true)
-    } else if class_name.contains("font-green") {
-        (true, true)
-    } else {
-        (false, false)
-    };
+    let status = sitedata::verdict(SITE, &class_name).unwrap_or(Status::Waiting);
+    let is_done = status != Status::Waiting;
     let verdict = verdict_text;
-    clear(last_verdict.len());
-    let mut stdout = std::io::stdout();
-    let _ = execute!(
-        stdout,
-        SetForegroundColor(if !is_done {
-            Color::Yellow
-        } else if is_accepted {
-            Color::Green
-        } else {
-            Color::Red
-        })
-    );
-    print!("{}", verdict);
-    let _ = execute!(stdout, ResetColor);
     if is_done {
-        println!();
+        submission::finish(&verdict, status, Details::default());
         return Ok(true);
     }
+    submission::verdict(&verdict, status);
     *last_verdict = verdict;
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     Ok(false)
 }
 
-fn escape_html(source: &String) -> String {
+fn escape_html(source: &str) -> String {
     source
         .replace("&", "&amp;")
         .replace("<", "&lt;")
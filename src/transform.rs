@@ -0,0 +1,422 @@
+use crate::bundle;
+use crate::config;
+use regex::Regex;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// One step of the pipeline in `submitter.json`, applied to the sites listed in `sites` or to all.
+#[derive(Deserialize, Clone)]
+pub struct Transform {
+    #[serde(flatten)]
+    pub kind: Kind,
+    #[serde(default)]
+    pub sites: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum Kind {
+    /// Inlines local headers of C and C++ sources.
+    Bundle,
+    /// Drops `#ifdef LOCAL` blocks and keeps the `#ifndef LOCAL` ones.
+    StripLocal,
+    RemoveFreopen,
+    /// Puts `text` at the top of the source.
+    Header {
+        text: String,
+    },
+    /// Removes a byte order mark and turns CRLF line endings into LF.
+    Normalize,
+    /// Renames the Java class or Kotlin object holding `main`.
+    MainClass {
+        #[serde(default = "main_class")]
+        class: String,
+    },
+    /// Pipes the source through a shell command.
+    Command {
+        command: String,
+    },
+}
+
+pub struct Transformed {
+    pub source: String,
+    pub warnings: Vec<String>,
+    /// Line map of the bundled source, dropped when a later transform changes the source.
+    pub bundle: Option<bundle::Bundle>,
+}
+
+fn main_class() -> String {
+    "Main".to_string()
+}
+
+/// Transforms used when `submitter.json` lists none.
+fn defaults() -> Vec<Transform> {
+    vec![Transform {
+        kind: Kind::Bundle,
+        sites: Vec::new(),
+    }]
+}
+
+/// Runs the configured transforms in order over `source`, read from `file` and going to `site`.
+pub fn apply(file: &Path, site: Option<&str>, source: String) -> Result<Transformed, String> {
+    let transforms = config::get().transforms.clone().unwrap_or_else(defaults);
+    let mut result = Transformed {
+        source,
+        warnings: Vec::new(),
+        bundle: None,
+    };
+    for transform in transforms {
+        if !transform.sites.is_empty()
+            && !site.is_some_and(|site| transform.sites.iter().any(|name| name == site))
+        {
+            continue;
+        }
+        let source = match transform.kind {
+            Kind::Bundle => {
+                if !bundle::applies(file) {
+                    continue;
+                }
+                let mut bundle = bundle::bundle(file, &result.source)?;
+                result.warnings.append(&mut bundle.warnings);
+                if bundle.inlined == 0 {
+                    continue;
+                }
+                result.source = bundle.source.clone();
+                result.bundle = Some(bundle);
+                continue;
+            }
+            Kind::StripLocal => strip_local(&result.source),
+            Kind::RemoveFreopen => remove_freopen(&result.source),
+            Kind::Header { text } => format!("{}\n{}", text.trim_end_matches('\n'), result.source),
+            Kind::Normalize => result
+                .source
+                .trim_start_matches('\u{feff}')
+                .replace("\r\n", "\n"),
+            Kind::MainClass { class } => match rename_main(file, &result.source, &class) {
+                Ok(source) => source,
+                Err(warning) => {
+                    result.warnings.push(warning);
+                    continue;
+                }
+            },
+            Kind::Command { command } => run(&command, file, site, &result.source)?,
+        };
+        if source != result.source {
+            result.source = source;
+            result.bundle = None;
+        }
+    }
+    Ok(result)
+}
+
+/// An open conditional while stripping LOCAL blocks.
+struct Conditional {
+    /// Tests LOCAL, so its directives are dropped.
+    local: bool,
+    /// The current branch is kept.
+    keep: bool,
+    /// A branch of a LOCAL block was kept, the ones after it are dropped.
+    taken: bool,
+    /// An `#elif` of a LOCAL block became the `#if` of the branches after it, which are kept
+    /// with their directives.
+    reopened: bool,
+}
+
+/// Drops the branches of conditionals on LOCAL that are not compiled without it. `#ifdef`,
+/// `#ifndef` and `#if [!]defined(LOCAL)` are understood, LOCAL in any other condition is left
+/// as it is.
+fn strip_local(source: &str) -> String {
+    let directive = Regex::new(r"^\s*#\s*(ifdef|ifndef|if|else|elif|endif)\b(.*)").unwrap();
+    let defined = Regex::new(r"^(!)?\s*defined\s*(?:\(\s*LOCAL\s*\)|\s+LOCAL)$").unwrap();
+    let kept = |stack: &[Conditional]| stack.iter().all(|open| open.keep);
+    let mut stack: Vec<Conditional> = Vec::new();
+    let mut result = String::new();
+    for line in source.split_inclusive('\n') {
+        let visible = kept(&stack);
+        let Some(caps) = directive.captures(line) else {
+            if visible {
+                result.push_str(line);
+            }
+            continue;
+        };
+        let condition = caps[2].trim();
+        // Whether the branch is compiled with LOCAL defined, for conditionals on LOCAL.
+        let on_local = match &caps[1] {
+            "ifdef" | "ifndef" => {
+                (condition.split_whitespace().next() == Some("LOCAL")).then(|| &caps[1] == "ifdef")
+            }
+            "if" => defined
+                .captures(condition)
+                .map(|caps| caps.get(1).is_none()),
+            _ => None,
+        };
+        match &caps[1] {
+            "ifdef" | "ifndef" | "if" => {
+                let local = on_local.is_some();
+                let keep = on_local != Some(true);
+                stack.push(Conditional {
+                    local,
+                    keep,
+                    taken: local && keep,
+                    reopened: false,
+                });
+                if local {
+                    continue;
+                }
+            }
+            "elif" | "else" => {
+                if let Some(open) = stack.last_mut().filter(|open| open.local && !open.reopened) {
+                    open.keep = !open.taken;
+                    open.taken = true;
+                    if &caps[1] == "elif" && open.keep {
+                        open.reopened = true;
+                        if kept(&stack) {
+                            result.push_str(&line.replacen("elif", "if", 1));
+                        }
+                    }
+                    continue;
+                }
+            }
+            _ => {
+                if let Some(open) = stack.pop() {
+                    if open.local && !open.reopened {
+                        continue;
+                    }
+                }
+            }
+        }
+        if visible {
+            result.push_str(line);
+        }
+    }
+    result
+}
+
+fn remove_freopen(source: &str) -> String {
+    let call = Regex::new(r"\b(std::)?freopen\s*\([^;]*\)\s*;").unwrap();
+    let mut result = String::new();
+    for line in source.split_inclusive('\n') {
+        if !call.is_match(line) {
+            result.push_str(line);
+            continue;
+        }
+        let rest = call.replace_all(line, "");
+        if !rest.trim().is_empty() {
+            result.push_str(&rest);
+        }
+    }
+    result
+}
+
+/// Which bytes of `source` are code, outside comments and string or character literals.
+fn code_mask(source: &str) -> Vec<bool> {
+    let bytes = source.as_bytes();
+    let find = |from: usize, needle: &[u8]| {
+        bytes[from.min(bytes.len())..]
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .map_or(bytes.len(), |position| from + position + needle.len())
+    };
+    let mut code = vec![true; bytes.len()];
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let end = if rest.starts_with(b"//") {
+            find(i, b"\n")
+        } else if rest.starts_with(b"/*") {
+            find(i + 2, b"*/")
+        } else if rest.starts_with(b"\"\"\"") {
+            find(i + 3, b"\"\"\"")
+        } else if rest[0] == b'"' || rest[0] == b'\'' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != rest[0] && bytes[j] != b'\n' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            (j + 1).min(bytes.len())
+        } else {
+            i += 1;
+            continue;
+        };
+        code[i..end].fill(false);
+        i = end;
+    }
+    code
+}
+
+/// Text between the first brace in code after `from` and the brace closing it.
+fn body<'a>(source: &'a str, code: &[bool], from: usize) -> Option<&'a str> {
+    let mut depth = 0;
+    let mut open = None;
+    for (i, byte) in source.bytes().enumerate().skip(from) {
+        if !code[i] {
+            continue;
+        }
+        match byte {
+            b'{' => {
+                open.get_or_insert(i);
+                depth += 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&source[open? + 1..i]);
+                }
+            }
+            b';' if open.is_none() => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Renames the class or object declaring `main`, and the references to it by `Name.`, `Name::` and
+/// `new Name(`. Comments and literals are left alone, and the source is kept as it is when the name
+/// is used in code any other way, since that use may or may not mean the class.
+fn rename_main(file: &Path, source: &str, class: &str) -> Result<String, String> {
+    let missing = || format!("No class with main found to rename to {}", class);
+    let (declaration, main) = match file.extension().and_then(|extension| extension.to_str()) {
+        Some("java") => (r"\bclass\s+(\w+)", r"\bvoid\s+main\s*\("),
+        Some("kt") => (r"\bobject\s+(\w+)", r"\bfun\s+main\s*\("),
+        _ => return Err(missing()),
+    };
+    let main = Regex::new(main).unwrap();
+    let code = code_mask(source);
+    let in_code = |range: std::ops::Range<usize>| code[range].iter().all(|&code| code);
+    let declared = Regex::new(declaration)
+        .unwrap()
+        .captures_iter(source)
+        .filter(|caps| in_code(caps.get(0).unwrap().range()))
+        .find(|caps| {
+            body(source, &code, caps.get(0).unwrap().end()).is_some_and(|body| main.is_match(body))
+        })
+        .ok_or_else(missing)?
+        .get(1)
+        .unwrap();
+    let name = regex::escape(declared.as_str());
+    let references = Regex::new(&format!(
+        r"\b({name})\s*(?:\.|::)|\bnew\s+({name})\s*\(",
+        name = name
+    ))
+    .unwrap();
+    let mut ranges = vec![declared.range()];
+    for caps in references.captures_iter(source) {
+        let name = caps.get(1).or_else(|| caps.get(2)).unwrap();
+        if in_code(caps.get(0).unwrap().range()) && !ranges.contains(&name.range()) {
+            ranges.push(name.range());
+        }
+    }
+    let word = Regex::new(&format!(r"\b{}\b", name)).unwrap();
+    if let Some(other) = word
+        .find_iter(source)
+        .find(|found| in_code(found.range()) && !ranges.contains(&found.range()))
+    {
+        return Err(format!(
+            "Not renaming {} to {}, it is also used on line {}",
+            declared.as_str(),
+            class,
+            source[..other.start()].matches('\n').count() + 1
+        ));
+    }
+    ranges.sort_by_key(|range| range.start);
+    let mut result = source.to_string();
+    for range in ranges.into_iter().rev() {
+        result.replace_range(range, class);
+    }
+    Ok(result)
+}
+
+fn run(command: &str, file: &Path, site: Option<&str>, source: &str) -> Result<String, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("SUBMITTER_FILE", file)
+        .env("SUBMITTER_SITE", site.unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Failed to run {}: {}", command, err))?;
+    // Written from a thread so a command producing output before reading all input cannot block.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("Failed to run {}: {}", command, err))?;
+    let _ = writer.join();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut message = format!("Transform {} failed with {}", command, output.status);
+        if !stderr.trim().is_empty() {
+            message = format!("{}: {}", message, stderr.trim());
+        }
+        return Err(message);
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("Transform {} printed non-UTF-8", command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_local_keeps_what_compiles_without_local() {
+        let source =
+            "a\n#ifdef LOCAL\nb\n#else\nc\n#endif\n#ifndef LOCAL\nd\n#else\ne\n#endif\nf\n";
+        assert_eq!(strip_local(source), "a\nc\nd\nf\n");
+    }
+
+    #[test]
+    fn strip_local_understands_defined() {
+        let source = "#if defined(LOCAL)\na\n#endif\n#if !defined LOCAL\nb\n#endif\n";
+        assert_eq!(strip_local(source), "b\n");
+        let other = "#if defined(LOCAL) && X\na\n#endif\n";
+        assert_eq!(strip_local(other), other);
+    }
+
+    #[test]
+    fn strip_local_turns_elif_into_if() {
+        let source = "#ifdef LOCAL\na\n#elif X\nb\n#else\nc\n#endif\n";
+        assert_eq!(strip_local(source), "#if X\nb\n#else\nc\n#endif\n");
+        let kept = "#ifndef LOCAL\na\n#elif X\nb\n#else\nc\n#endif\n";
+        assert_eq!(strip_local(kept), "a\n");
+    }
+
+    #[test]
+    fn strip_local_keeps_other_conditionals() {
+        let source = "#ifdef X\n#ifdef LOCAL\na\n#endif\nb\n#else\nc\n#endif\n";
+        assert_eq!(strip_local(source), "#ifdef X\nb\n#else\nc\n#endif\n");
+    }
+
+    #[test]
+    fn remove_freopen_drops_calls() {
+        let source = "int main() {\n  freopen(\"in\", \"r\", stdin);\n  std::freopen(\"out\", \"w\", stdout); int x;\n}\n";
+        assert_eq!(remove_freopen(source), "int main() {\n   int x;\n}\n");
+    }
+
+    #[test]
+    fn rename_main_renames_the_class_and_its_references() {
+        let source = "public class Solution {\n  public static void main(String[] args) {\n    new Solution().go(); Solution.run(); // Solution\n  }\n}\n";
+        assert_eq!(
+            rename_main(Path::new("Solution.java"), source, "Main").unwrap(),
+            "public class Main {\n  public static void main(String[] args) {\n    new Main().go(); Main.run(); // Solution\n  }\n}\n"
+        );
+        let kotlin = "object Solution {\n  @JvmStatic fun main(args: Array<String>) {}\n}\n";
+        assert_eq!(
+            rename_main(Path::new("a.kt"), kotlin, "Main").unwrap(),
+            "object Main {\n  @JvmStatic fun main(args: Array<String>) {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn rename_main_refuses_other_uses() {
+        let source = "class Solution {\n  static Solution s;\n  public static void main(String[] args) {}\n}\n";
+        assert_eq!(
+            rename_main(Path::new("Solution.java"), source, "Main"),
+            Err("Not renaming Solution to Main, it is also used on line 2".to_string())
+        );
+        assert!(rename_main(Path::new("a.java"), "class A {}\n", "Main").is_err());
+    }
+}
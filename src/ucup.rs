@@ -1,40 +1,51 @@
-use crate::{clear, set_value};
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::set_value;
+use crate::sitedata::{self, Status};
+use crate::submission;
 use dialoguer::{Input, Password};
 use thirtyfour::error::{WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver.goto("https://contest.ucup.ac/").await?;
+const SITE: &str = "ucup";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     driver.delete_all_cookies().await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver.goto("https://contest.ucup.ac/login").await?;
-    if driver.current_url().await?.as_str() != "https://contest.ucup.ac/login" {
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
+    Ok(driver.current_url().await?.as_str() != crate::site_url(&sitedata::url(SITE, "login")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your universal cup login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your universal cup password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("input-username"))
+        .find(sitedata::by(SITE, "login"))
         .await?
         .send_keys(login)
         .await?;
     driver
-        .find(By::Id("input-password"))
+        .find(sitedata::by(SITE, "password"))
         .await?
         .send_keys(password)
         .await?;
-    driver.find(By::Id("button-submit")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "login_button"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
     Ok(driver.get_all_cookies().await?)
 }
@@ -45,13 +56,13 @@ pub async fn submit(
     language: String,
     source: String,
 ) -> WebDriverResult<()> {
-    driver.goto(&url).await?;
+    crate::goto(driver, &url).await?;
     driver
-        .find(By::PartialLinkText("Submit"))
+        .find(sitedata::by(SITE, "submit_tab"))
         .await?
         .click()
         .await?;
-    let language_selector = driver.find(By::Id("input-answer_answer_language")).await?;
+    let language_selector = driver.find(sitedata::by(SITE, "language")).await?;
     let options = language_selector.find_all(By::Tag("option")).await?;
     let mut result = "".to_string();
     for option in options {
@@ -63,18 +74,41 @@ pub async fn submit(
         }
     }
     if result.is_empty() {
-        println!("Language not found");
+        submission::error("Language not found");
+        return Ok(());
+    }
+    set_value(driver, language_selector, result.clone()).await?;
+    let source_code = driver.find(sitedata::by(SITE, "source")).await?;
+    set_value(driver, source_code.clone(), source).await?;
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let form = guard::Form {
+        language: Some(crate::selected_label(driver, language_field.clone()).await?),
+        language_matches: language_field.value().await? == Some(result),
+        source: source_code.value().await?.unwrap_or_default(),
+    };
+    if !guard::ready(driver, form).await? {
         return Ok(());
     }
-    set_value(driver, language_selector, result).await?;
-    let source_code = driver.find(By::Id("input-answer_answer_editor")).await?;
-    set_value(driver, source_code, source).await?;
     driver
-        .find(By::Id("button-submit-answer"))
+        .find(sitedata::by(SITE, "submit_button"))
         .await?
         .click()
         .await?;
     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    follow(driver).await
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    if !url.contains("/submission/") {
+        submission::error("Bad submission url");
+        return Ok(());
+    }
+    crate::goto(driver, &url).await?;
+    follow(driver).await
+}
+
+/// Polls the submission shown on the open page until its verdict is final.
+async fn follow(driver: &WebDriver) -> WebDriverResult<()> {
     let mut last_verdict = "".to_string();
     loop {
         match iteration(driver, &mut last_verdict).await {
@@ -84,7 +118,7 @@ pub async fn submit(
                     continue;
                 }
                 _ => {
-                    println!("Error while checking verdict");
+                    submission::error("Error while checking verdict");
                     break;
                 }
             },
@@ -95,45 +129,49 @@ pub async fn submit(
 }
 
 async fn iteration(driver: &WebDriver, last_submit: &mut String) -> WebDriverResult<bool> {
-    let mut stdout = std::io::stdout();
-    match driver.find(By::ClassName("info")).await {
+    match driver.find(sitedata::by(SITE, "info")).await {
         Ok(info) => {
             let verdict = info
-                .find(By::ClassName("uoj-status-details-text-div"))
+                .find(sitedata::by(SITE, "status_text"))
                 .await?
                 .text()
                 .await?;
-            clear(last_submit.len());
-            let _ = execute!(stdout, SetForegroundColor(Color::Yellow));
-            print!("{}", verdict);
-            let _ = execute!(stdout, ResetColor);
+            submission::verdict(&verdict, Status::Waiting);
             *last_submit = verdict;
             Ok(false)
         }
         Err(_) => {
             let verdict = driver
-                .find(By::ClassName("uoj-score"))
+                .find(sitedata::by(SITE, "score"))
                 .await?
                 .text()
                 .await?;
-            clear(last_submit.len());
-            let _ = execute!(
-                stdout,
-                SetForegroundColor(if verdict.starts_with("AC") {
-                    Color::Green
-                } else {
-                    Color::Red
-                })
-            );
-            println!("{}", verdict);
-            let _ = execute!(stdout, ResetColor);
+            let status = match sitedata::verdict(SITE, &verdict) {
+                Some(Status::Accepted) => Status::Accepted,
+                _ => Status::Rejected,
+            };
+            let mut details = Details {
+                score: Some(verdict.clone()),
+                ..Default::default()
+            };
             let rows = driver.find_all(By::Tag("tr")).await?;
             if rows.len() >= 2 {
                 let link = rows[1].find(By::Tag("a")).await?;
                 if let Some(link) = link.attr("href").await? {
-                    println!("Submission url https://contest.ucup.ac{}", link);
+                    submission::submitted(
+                        SITE,
+                        &format!("{}{}", sitedata::url(SITE, "origin"), link),
+                    );
+                }
+                let cols = rows[1].find_all(By::Tag("td")).await?;
+                if let Some(col) = cols.get(sitedata::index(SITE, "time_column")) {
+                    details.time = Some(col.text().await?);
+                }
+                if let Some(col) = cols.get(sitedata::index(SITE, "memory_column")) {
+                    details.memory = Some(col.text().await?);
                 }
             }
+            submission::finish(&verdict, status, details);
             Ok(true)
         }
     }
@@ -0,0 +1,210 @@
+use crate::output;
+use crate::sitedata;
+use base64::Engine;
+use http::Method;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use thirtyfour::common::command::{Command, ExtensionCommand};
+use thirtyfour::error::WebDriverResult;
+use thirtyfour::{WebDriver, WebElement};
+
+/// In automatic mode sources from this size on are uploaded, site editors get slow with them.
+const THRESHOLD: usize = 32 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Auto,
+    Always,
+    Never,
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+static FILE_NAME: OnceLock<String> = OnceLock::new();
+
+pub fn set_mode(mode: Mode) {
+    let _ = MODE.set(mode);
+}
+
+/// Name the uploaded file gets, the one of the local file.
+pub fn set_file(file: &str) {
+    let name = Path::new(file)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string());
+    let _ = FILE_NAME.set(name);
+}
+
+/// Whether `source` goes through the file input of `site` instead of its editor.
+pub fn wanted(site: &str, source: &str) -> bool {
+    let supported = sitedata::has_selector(site, "source_file");
+    match MODE.get().copied().unwrap_or(Mode::Auto) {
+        Mode::Never => false,
+        Mode::Auto => supported && source.len() >= THRESHOLD,
+        Mode::Always => {
+            if !supported {
+                output::warning(&format!("No file upload on {}, using the editor", site));
+            }
+            supported
+        }
+    }
+}
+
+/// Selenium's own command for copying a file to the machine the browser runs on, the
+/// browser cannot see local paths when it runs in docker.
+#[derive(Debug)]
+struct UploadFile {
+    /// Base64 of a zip archive holding the single file.
+    file: String,
+}
+
+impl ExtensionCommand for UploadFile {
+    fn parameters_json(&self) -> Option<Value> {
+        Some(json!({ "file": self.file }))
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Arc<str> {
+        Arc::from("/se/file")
+    }
+}
+
+/// Uploads `source` and selects it in the file input `element`.
+pub async fn attach(driver: &WebDriver, element: &WebElement, source: &str) -> WebDriverResult<()> {
+    let name = FILE_NAME.get().map(String::as_str).unwrap_or("solution");
+    let archive = zip(name, source.as_bytes());
+    let command = UploadFile {
+        file: base64::engine::general_purpose::STANDARD.encode(archive),
+    };
+    let path: String = driver
+        .handle
+        .cmd(Command::ExtensionCommand(Box::new(command)))
+        .await?
+        .value()?;
+    element.send_keys(path).await
+}
+
+/// Contents of the file selected in `element`, as the browser is going to send them.
+pub async fn read_back(driver: &WebDriver, element: &WebElement) -> WebDriverResult<String> {
+    let value = driver
+        .execute_async(
+            "var done = arguments[arguments.length - 1];\
+            var file = arguments[0].files[0];\
+            if (!file) { done(''); return; }\
+            file.text().then(done, function () { done(''); });",
+            vec![element.to_json()?],
+        )
+        .await?;
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Zip archive with one stored, uncompressed file, which is all the upload command needs.
+fn zip(name: &str, data: &[u8]) -> Vec<u8> {
+    let crc = crc32(data);
+    let size = data.len() as u32;
+    // Version 2.0, no flags, stored, 1980-01-01 00:00, crc and sizes, name length, no extra field.
+    let mut fields = Vec::new();
+    for value in [20u16, 0, 0, 0, 0x21] {
+        fields.extend(value.to_le_bytes());
+    }
+    for value in [crc, size, size] {
+        fields.extend(value.to_le_bytes());
+    }
+    fields.extend((name.len() as u16).to_le_bytes());
+    fields.extend(0u16.to_le_bytes());
+
+    let mut archive = Vec::new();
+    archive.extend(0x0403_4b50u32.to_le_bytes());
+    archive.extend(&fields);
+    archive.extend(name.as_bytes());
+    archive.extend(data);
+
+    let directory_offset = archive.len() as u32;
+    archive.extend(0x0201_4b50u32.to_le_bytes());
+    archive.extend(20u16.to_le_bytes());
+    archive.extend(&fields);
+    // No comment, disk 0, no attributes, local header at offset 0.
+    for value in [0u16, 0, 0] {
+        archive.extend(value.to_le_bytes());
+    }
+    for value in [0u32, 0] {
+        archive.extend(value.to_le_bytes());
+    }
+    archive.extend(name.as_bytes());
+    let directory_size = archive.len() as u32 - directory_offset;
+
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    for value in [0u16, 0, 1, 1] {
+        archive.extend(value.to_le_bytes());
+    }
+    archive.extend(directory_size.to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn zip_stores_one_file() {
+        let data = b"int main() {}\n";
+        let archive = zip("main.cpp", data);
+        // Local header, name and the data as is.
+        assert_eq!(u32_at(&archive, 0), 0x0403_4b50);
+        assert_eq!(u16_at(&archive, 8), 0);
+        assert_eq!(u32_at(&archive, 14), crc32(data));
+        assert_eq!(u32_at(&archive, 18), data.len() as u32);
+        assert_eq!(u16_at(&archive, 26), 8);
+        assert_eq!(&archive[30..38], b"main.cpp");
+        assert_eq!(&archive[38..38 + data.len()], data);
+        // The end record points at the central directory, which points back at the local header.
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), 0x0605_4b50);
+        assert_eq!(u16_at(&archive, end + 10), 1);
+        let directory = u32_at(&archive, end + 16) as usize;
+        assert_eq!(directory, 38 + data.len());
+        assert_eq!(u32_at(&archive, end + 12) as usize, end - directory);
+        assert_eq!(u32_at(&archive, directory), 0x0201_4b50);
+        assert_eq!(u32_at(&archive, directory + 16), crc32(data));
+        assert_eq!(u32_at(&archive, directory + 42), 0);
+        assert_eq!(&archive[directory + 46..end], b"main.cpp");
+    }
+}
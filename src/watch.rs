@@ -0,0 +1,177 @@
+use crate::inflight::{self, Entry};
+use crate::output;
+use crate::output::{color, paint};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::style::Color;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use regex::Regex;
+use std::io::{IsTerminal, Stdout, Write};
+use std::time::Duration;
+
+/// Path segments that say nothing about which problem it is.
+const NOISE: [&str; 7] = [
+    "contest",
+    "contests",
+    "problem",
+    "problems",
+    "problemset",
+    "tasks",
+    "p",
+];
+
+/// `90` is minutes, `1:30` is hours and minutes, `1:30:00` adds seconds.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let parts: Vec<u64> = value
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [minutes] => Some(minutes * 60),
+        [hours, minutes] => Some(hours * 3600 + minutes * 60),
+        [hours, minutes, seconds] => Some(hours * 3600 + minutes * 60 + seconds),
+        _ => None,
+    }
+}
+
+pub fn run(ends_at: Option<u64>) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        output::error("watch needs a terminal");
+        return Ok(());
+    }
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let result = watch(&mut stdout, ends_at);
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn watch(stdout: &mut Stdout, ends_at: Option<u64>) -> std::io::Result<()> {
+    loop {
+        draw(stdout, &inflight::load(), ends_at)?;
+        if !event::poll(Duration::from_millis(500))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(stdout: &mut Stdout, entries: &[Entry], ends_at: Option<u64>) -> std::io::Result<()> {
+    let now = inflight::now();
+    let (width, _) = terminal::size()?;
+    let mut lines = Vec::new();
+    let title = "Submissions".to_string();
+    lines.push(match ends_at {
+        Some(ends_at) if ends_at > now => {
+            let left = ends_at - now;
+            let countdown = format!("Contest ends in {}", clock(left));
+            let countdown = if left < 600 {
+                paint(&countdown, Color::Yellow)
+            } else {
+                countdown
+            };
+            format!("{}    {}", title, countdown)
+        }
+        Some(_) => format!("{}    {}", title, paint("Contest is over", Color::Red)),
+        None => title,
+    });
+    lines.push(String::new());
+    lines.push(format!(
+        "{:10} {:16} {:10} {:8} {:>8}  {}",
+        "Site", "Problem", "Language", "Test", "Elapsed", "Verdict"
+    ));
+    if entries.is_empty() {
+        lines.push("No submissions yet, submit from another terminal".to_string());
+    }
+    for entry in entries {
+        let elapsed = entry.finished.unwrap_or(now).saturating_sub(entry.started);
+        lines.push(format!(
+            "{:10} {:16} {:10} {:8} {:>8}  {}",
+            fit(&entry.site, 10),
+            fit(&problem(&entry.problem), 16),
+            fit(&entry.language, 10),
+            progress(&entry.verdict),
+            clock(elapsed),
+            paint(&entry.verdict, color(entry.status)),
+        ));
+    }
+    lines.push(String::new());
+    lines.push("q to quit".to_string());
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, MoveTo(0, row as u16))?;
+        // Colored text can't be cut safely, only plain lines are clipped.
+        if line.contains('\x1b') || width == 0 {
+            write!(stdout, "{}", line)?;
+        } else {
+            write!(stdout, "{}", fit(line, width as usize))?;
+        }
+    }
+    stdout.flush()
+}
+
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut text: String = text.chars().take(width.saturating_sub(1)).collect();
+    text.push('…');
+    text
+}
+
+/// `https://codeforces.com/contest/1850/problem/A` becomes `1850/A`.
+fn problem(url: &str) -> String {
+    let path = url.split("://").last().unwrap_or(url);
+    path.split(['/', '?'])
+        .skip(1)
+        .filter(|segment| !segment.is_empty() && !NOISE.contains(segment))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Test number or passed/total count mentioned in a verdict.
+fn progress(verdict: &str) -> String {
+    let count = Regex::new(r"(\d+)\s*/\s*(\d+)").unwrap();
+    if let Some(caps) = count.captures(verdict) {
+        return format!("{}/{}", &caps[1], &caps[2]);
+    }
+    let test = Regex::new(r"(?i)test\s*#?(\d+)").unwrap();
+    match test.captures(verdict) {
+        Some(caps) => caps[1].to_string(),
+        None => String::new(),
+    }
+}
+
+fn clock(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_reads_minutes_and_hours() {
+        assert_eq!(parse_duration("90"), Some(5400));
+        assert_eq!(parse_duration("1:30"), Some(5400));
+        assert_eq!(parse_duration("2:00:30"), Some(7230));
+        assert_eq!(parse_duration("1:2:3:4"), None);
+        assert_eq!(parse_duration("1h"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+}
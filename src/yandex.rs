@@ -1,57 +1,77 @@
-use crate::{clear, set_value};
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
-use dialoguer::console::Term;
+use crate::guard;
+use crate::output::{self, Details};
+use crate::set_value;
+use crate::sitedata::{self, Status};
+use crate::submission;
+use crate::upload;
 use dialoguer::{Input, Password};
+use regex::Regex;
 use thirtyfour::error::{WebDriverErrorInner, WebDriverResult};
 use thirtyfour::{By, Cookie, Key, WebDriver};
 
-pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
-    driver
-        .goto("https://contest.yandex.com/contest/3/problems/B/")
-        .await?;
+const SITE: &str = "yandex";
+
+pub async fn restore(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<bool> {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
     for cookie in cookies {
         driver.add_cookie(cookie).await?;
     }
-    driver
-        .goto("https://contest.yandex.com/contest/3/problems/B/")
-        .await?;
-    if !driver.source().await?.contains("log in") {
+    crate::goto(driver, &sitedata::url(SITE, "home")).await?;
+    Ok(!driver
+        .source()
+        .await?
+        .contains(sitedata::text(SITE, "logged_out")))
+}
+
+pub async fn login(driver: &WebDriver, cookies: Vec<Cookie>) -> WebDriverResult<Vec<Cookie>> {
+    if restore(driver, cookies).await? {
         return Ok(driver.get_all_cookies().await?);
     }
-    driver.goto("https://passport.yandex.com/auth?origin=contest&retpath=http://contest.yandex.com/contest/3/enter/?retPage=").await?;
+    crate::goto(driver, &sitedata::url(SITE, "login")).await?;
     let login: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your yandex login")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("passp-field-login"))
+        .find(sitedata::by(SITE, "login"))
         .await?
         .send_keys(login)
         .await?;
-    driver.find(By::Id("passp:sign-in")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "sign_in"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     let password: String = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your yandex password")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("passp-field-passwd"))
+        .find(sitedata::by(SITE, "password"))
         .await?
         .send_keys(password)
         .await?;
-    driver.find(By::Id("passp:sign-in")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "sign_in"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
     let confirmation: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
         .with_prompt("Enter your confirmation code from email")
-        .interact_on(&Term::stdout())
+        .interact_on(&output::term())
         .unwrap();
     driver
-        .find(By::Id("passp-field-confirmation-code"))
+        .find(sitedata::by(SITE, "confirmation"))
         .await?
         .send_keys(confirmation)
         .await?;
-    driver.find(By::ClassName("Button2")).await?.click().await?;
+    driver
+        .find(sitedata::by(SITE, "confirm_button"))
+        .await?
+        .click()
+        .await?;
     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
     Ok(driver.get_all_cookies().await?)
 }
@@ -62,8 +82,8 @@ pub async fn submit(
     language: String,
     source: String,
 ) -> WebDriverResult<()> {
-    driver.goto(&url).await?;
-    let language_selector = driver.find(By::ClassName("select__control")).await?;
+    crate::goto(driver, &url).await?;
+    let language_selector = driver.find(sitedata::by(SITE, "language")).await?;
     let options = language_selector.find_all(By::Tag("option")).await?;
     let mut value = "".to_string();
     for option in options {
@@ -78,28 +98,48 @@ pub async fn submit(
         }
     }
     if value.is_empty() {
-        println!("Language not found");
+        submission::error("Language not found");
         return Ok(());
     }
-    set_value(driver, language_selector.clone(), value).await?;
+    set_value(driver, language_selector.clone(), value.clone()).await?;
     driver
         .action_chain()
         .send_keys(Key::PageDown)
         .perform()
         .await?;
-    let radio_button = driver.find(By::ClassName("radio-button__control")).await?;
+    let use_file = upload::wanted(SITE, &source);
+    let radio_buttons = driver.find_all(sitedata::by(SITE, "radio")).await?;
+    let radio_index = if use_file {
+        sitedata::index(SITE, "file_radio")
+    } else {
+        0
+    };
+    let Some(radio_button) = radio_buttons.get(radio_index) else {
+        submission::error("Failed to find the source mode switch");
+        return Ok(());
+    };
     radio_button.focus().await?;
     radio_button.send_keys(Key::Space).await?;
-    driver
-        .execute(
-            "\
-        var textArea = document.getElementsByClassName('input__control')[0];\
-        var editor = CodeMirror.fromTextArea(textArea);\
-        editor.getDoc().setValue(arguments[0]);\
-    ",
-            vec![serde_json::to_value(source).unwrap()],
-        )
-        .await?;
+    let file_input = if use_file {
+        let file_input = driver.find(sitedata::by(SITE, "source_file")).await?;
+        upload::attach(driver, &file_input, &source).await?;
+        Some(file_input)
+    } else {
+        driver
+            .execute(
+                "\
+            var textArea = document.querySelector(arguments[1]);\
+            var editor = CodeMirror.fromTextArea(textArea);\
+            editor.getDoc().setValue(arguments[0]);\
+        ",
+                vec![
+                    serde_json::to_value(source).unwrap(),
+                    serde_json::to_value(sitedata::selector(SITE, "source")).unwrap(),
+                ],
+            )
+            .await?;
+        None
+    };
     driver
         .action_chain()
         .send_keys(Key::PageDown)
@@ -116,17 +156,51 @@ pub async fn submit(
         .send_keys(Key::PageDown)
         .perform()
         .await?;
+    let language_field = driver.find(sitedata::by(SITE, "language")).await?;
+    let form = guard::Form {
+        language: Some(crate::selected_label(driver, language_field.clone()).await?),
+        language_matches: language_field.value().await? == Some(value),
+        source: match &file_input {
+            Some(file_input) => upload::read_back(driver, file_input).await?,
+            None => editor_source(driver).await?,
+        },
+    };
+    if !guard::ready(driver, form).await? {
+        return Ok(());
+    }
     driver
-        .find(By::ClassName("problem__send"))
-        .await?
-        .find(By::Tag("button"))
+        .find(sitedata::by(SITE, "submit_button"))
         .await?
         .click()
         .await?;
     tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    follow(driver, None).await
+}
+
+pub async fn status(driver: &WebDriver, url: String) -> WebDriverResult<()> {
+    let regex = Regex::new(r"/contest/(\d+)/run-report/(\d+)").unwrap();
+    let Some(caps) = regex.captures(&url) else {
+        submission::error("Bad submission url");
+        return Ok(());
+    };
+    crate::goto(
+        driver,
+        &sitedata::url_with(SITE, "submits", &[("contest", &caps[1])]),
+    )
+    .await?;
+    let row = sitedata::by_with(SITE, "submission_row", &[("id", &caps[2])]);
+    if driver.find(row.clone()).await.is_err() {
+        submission::error("Submission not found among your submissions");
+        return Ok(());
+    }
+    follow(driver, Some(row)).await
+}
+
+/// Polls the verdict of the given row of the submissions table, or of the newest one.
+async fn follow(driver: &WebDriver, row: Option<By>) -> WebDriverResult<()> {
     let mut last_verdict = "".to_string();
     loop {
-        match single_iteration(driver, &mut last_verdict).await {
+        match single_iteration(driver, &row, &mut last_verdict).await {
             Ok(true) => break,
             Ok(false) => continue,
             Err(err) => match *err {
@@ -147,58 +221,66 @@ pub async fn submit(
     Ok(())
 }
 
-async fn single_iteration(driver: &WebDriver, last_verdict: &mut String) -> WebDriverResult<bool> {
-    let table = driver.find(By::ClassName("table")).await?;
-    let rows = table.find_all(By::Tag("tr")).await?;
-    if rows.len() < 2 {
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-        return Ok(false);
-    }
-    let row = &rows[1];
+/// Contents of the CodeMirror editor created over the source textarea.
+async fn editor_source(driver: &WebDriver) -> WebDriverResult<String> {
+    let value = driver
+        .execute(
+            "var textArea = document.querySelector(arguments[0]);\
+            var wrapper = textArea.nextSibling;\
+            return wrapper && wrapper.CodeMirror ? wrapper.CodeMirror.getValue() : textArea.value;",
+            vec![serde_json::to_value(sitedata::selector(SITE, "source")).unwrap()],
+        )
+        .await?;
+    Ok(value.json().as_str().unwrap_or_default().to_string())
+}
+
+async fn single_iteration(
+    driver: &WebDriver,
+    row: &Option<By>,
+    last_verdict: &mut String,
+) -> WebDriverResult<bool> {
+    let row = match row {
+        Some(row) => driver.find(row.clone()).await?,
+        None => {
+            let table = driver.find(sitedata::by(SITE, "status_table")).await?;
+            let rows = table.find_all(By::Tag("tr")).await?;
+            if rows.len() < 2 {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                return Ok(false);
+            }
+            rows[1].clone()
+        }
+    };
     let columns = row.find_all(By::Tag("td")).await?;
-    if columns.len() < 9 {
+    let verdict_column = sitedata::index(SITE, "verdict_column");
+    let test_column = sitedata::index(SITE, "test_column");
+    let link_column = sitedata::index(SITE, "link_column");
+    if columns.len() <= verdict_column.max(test_column) {
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         return Ok(false);
     }
-    let verdict = columns[4].find(By::ClassName("table__data")).await?;
+    let verdict = columns[verdict_column]
+        .find(sitedata::by(SITE, "verdict"))
+        .await?;
     let class_name = verdict.class_name().await?.unwrap_or("".to_string());
-    // println!("{:?}", class_name);
-    let (is_accepted, is_done) = if class_name.contains("table__data_mood_neg") {
-        (false, true)
-    } else if class_name.contains("table__data_mood_pos") {
-        (true, true)
-    } else {
-        (false, false)
-    };
+    let status = sitedata::verdict(SITE, &class_name).unwrap_or(Status::Waiting);
+    let is_done = status != Status::Waiting;
     let mut verdict = verdict.find(By::Tag("a")).await?.text().await?;
-    let test = columns[8].text().await?;
+    let test = columns[test_column].text().await?;
     if test.as_str() != "-" {
         verdict += &format!(" on test {}", test);
     }
-    clear(last_verdict.len());
-    let mut stdout = std::io::stdout();
-    let _ = execute!(
-        stdout,
-        SetForegroundColor(if !is_done {
-            Color::Yellow
-        } else if is_accepted {
-            Color::Green
-        } else {
-            Color::Red
-        })
-    );
-    print!("{}", verdict);
-    let _ = execute!(stdout, ResetColor);
     if is_done {
-        println!();
-        if columns.len() > 10 {
-            let link = columns[10].find(By::Tag("a")).await?;
+        if columns.len() > link_column {
+            let link = columns[link_column].find(By::Tag("a")).await?;
             if let Some(href) = link.attr("href").await? {
-                println!("Submission url https://contest.yandex.com{}", href);
+                submission::submitted(SITE, &format!("{}{}", sitedata::url(SITE, "origin"), href));
             }
         }
+        submission::finish(&verdict, status, Details::default());
         return Ok(true);
     }
+    submission::verdict(&verdict, status);
     *last_verdict = verdict;
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     Ok(false)